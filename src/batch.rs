@@ -0,0 +1,148 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use bevy::{
+    prelude::*,
+    tasks::{ComputeTaskPool, ParallelSlice},
+};
+
+use crate::{
+    decks::MatchDecks,
+    game::{AppState, SimulationConfig},
+    replay::{replay_path, Replay},
+    results::{GameResult, GameResults},
+    rules::RuleSet,
+    strategy::StrategyKind,
+};
+
+/// Games handed to a worker at a time, small enough to keep every core busy.
+const GAMES_PER_TASK: usize = 64;
+
+/// Plays every game to completion across all cores, without spawning entities.
+///
+/// Each game only depends on its own seed, so the results are the same as
+/// playing the games one after another.
+pub fn run_batch(
+    config: &SimulationConfig,
+    decks: &MatchDecks,
+    rules: &RuleSet,
+) -> Vec<GameResult> {
+    let strategies = [config.player_strategy, config.enemy_strategy];
+    run_batch_with(config, rules, |_| (decks, strategies))
+}
+
+/// Like `run_batch`, with each game played by the decks and AIs `seats`
+/// picks for its id.
+pub fn run_batch_with<'a>(
+    config: &SimulationConfig,
+    rules: &RuleSet,
+    seats: impl Fn(usize) -> (&'a MatchDecks, [StrategyKind; 2]) + Sync,
+) -> Vec<GameResult> {
+    let ids: Vec<usize> = (0..config.games).collect();
+    let finished = AtomicUsize::new(0);
+    let report_every = (config.games / 10).max(1);
+
+    ids.par_chunk_map(ComputeTaskPool::get(), GAMES_PER_TASK, |chunk| {
+        chunk
+            .iter()
+            .map(|&id| {
+                let (decks, strategies) = seats(id);
+                let (seed, mut state) = config.new_game(id, decks, strategies, rules);
+                state.run_to_completion();
+                if let Some(dir) = &config.replays {
+                    let replay = Replay::record(seed, &state, decks);
+                    if let Err(err) = replay.save(dir, id) {
+                        error!(
+                            "Failed to write {}: {}",
+                            replay_path(dir, id).display(),
+                            err
+                        );
+                    }
+                }
+                let done = finished.fetch_add(1, Ordering::Relaxed) + 1;
+                if done.is_multiple_of(report_every) {
+                    info!("Played {}/{} games", done, config.games);
+                }
+                state.result(id, seed)
+            })
+            .collect::<Vec<_>>()
+    })
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+pub fn run_batch_games(
+    mut commands: Commands,
+    config: Res<SimulationConfig>,
+    decks: Res<MatchDecks>,
+    rules: Res<RuleSet>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    commands.insert_resource(GameResults(run_batch(&config, &decks, &rules)));
+    if let Some(dir) = &config.replays {
+        info!("Wrote replays to {}", dir.display());
+    }
+    next_state.set(AppState::Finished);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::tasks::TaskPool;
+
+    use super::*;
+    use crate::{
+        cards::Card, cli::OutputFormat, decks::Deck, game::StepMode, seed::derive_seed,
+        sim::GameState, strategy::MctsBudget,
+    };
+
+    #[test]
+    fn batch_matches_serial_play() {
+        ComputeTaskPool::get_or_init(TaskPool::default);
+        let deck = Deck {
+            cards: [(1, 1), (2, 1), (1, 3), (3, 2)]
+                .iter()
+                .cycle()
+                .take(12)
+                .enumerate()
+                .map(|(i, &(damage, health))| Card::unit(&i.to_string(), damage, health))
+                .collect(),
+            health: 5,
+        };
+        let decks = MatchDecks {
+            player: deck.clone(),
+            enemy: deck,
+        };
+        let rules = RuleSet::default();
+        let config = SimulationConfig {
+            // Enough games to span several tasks
+            games: GAMES_PER_TASK * 3 + 1,
+            seed: 42,
+            format: OutputFormat::Text,
+            step_mode: StepMode::Batch,
+            player_strategy: StrategyKind::Random,
+            enemy_strategy: StrategyKind::Greedy,
+            mcts: MctsBudget {
+                iterations: 10,
+                time_budget: None,
+            },
+            csv: None,
+            json: None,
+            replays: None,
+            // Only read to decide whether to track cards
+            card_stats: Some("cards.csv".into()),
+        };
+
+        let serial: Vec<GameResult> = (0..config.games)
+            .map(|id| {
+                let seed = derive_seed(config.seed, id as u64);
+                let strategies = [config.player_strategy, config.enemy_strategy]
+                    .map(|kind| kind.build(config.mcts));
+                let mut state = GameState::deal(seed, &decks, strategies, &rules);
+                state.track_cards();
+                state.run_to_completion();
+                state.result(id, seed)
+            })
+            .collect();
+        assert_eq!(run_batch(&config, &decks, &rules), serial);
+    }
+}
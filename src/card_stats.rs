@@ -0,0 +1,175 @@
+//! How much each card contributes, summed over every game of a run.
+
+use std::{collections::BTreeMap, path::Path, sync::Arc};
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    game::SimulationConfig,
+    results::{ExportError, GameResult, GameResults},
+    sim::Side,
+};
+
+/// One side's copies of a card, totalled over every game.
+///
+/// Each side plays the same deck in every game, so cards in both decks get a
+/// row per side rather than mixing the two decks' win rates.
+#[derive(Serialize, Debug)]
+pub struct CardImpact {
+    pub side: Side,
+    pub card: String,
+    pub played: usize,
+    pub direct_damage: i32,
+    pub damage_absorbed: i32,
+    pub destroyed: usize,
+    /// Games where a side holding the card in its deck played it at least
+    /// once, and how many of those it won.
+    pub games_played: usize,
+    pub wins_played: usize,
+    pub win_rate_played: Option<f64>,
+    /// Games where a side holding the card in its deck never played it.
+    pub games_not_played: usize,
+    pub wins_not_played: usize,
+    pub win_rate_not_played: Option<f64>,
+}
+
+/// Sums up the card tallies of every game that tracked them, by side and
+/// card id.
+pub fn card_impacts(results: &[GameResult]) -> Vec<CardImpact> {
+    let mut impacts: BTreeMap<(usize, Arc<str>), CardImpact> = BTreeMap::new();
+    for result in results {
+        let Some(cards) = &result.cards else {
+            continue;
+        };
+        for side in [Side::Player, Side::Enemy] {
+            let won = result.winner == side;
+            for (id, tally) in &cards[side.index()] {
+                let impact = impacts
+                    .entry((side.index(), id.clone()))
+                    .or_insert_with(|| CardImpact::new(side, id));
+                impact.played += tally.played;
+                impact.direct_damage += tally.direct_damage;
+                impact.damage_absorbed += tally.damage_absorbed;
+                impact.destroyed += tally.destroyed;
+                if tally.played > 0 {
+                    impact.games_played += 1;
+                    impact.wins_played += won as usize;
+                } else if tally.in_deck {
+                    impact.games_not_played += 1;
+                    impact.wins_not_played += won as usize;
+                }
+            }
+        }
+    }
+
+    let rate = |wins: usize, games: usize| (games > 0).then(|| wins as f64 / games as f64);
+    impacts
+        .into_values()
+        .map(|impact| CardImpact {
+            win_rate_played: rate(impact.wins_played, impact.games_played),
+            win_rate_not_played: rate(impact.wins_not_played, impact.games_not_played),
+            ..impact
+        })
+        .collect()
+}
+
+impl CardImpact {
+    fn new(side: Side, card: &str) -> Self {
+        CardImpact {
+            side,
+            card: card.to_string(),
+            played: 0,
+            direct_damage: 0,
+            damage_absorbed: 0,
+            destroyed: 0,
+            games_played: 0,
+            wins_played: 0,
+            win_rate_played: None,
+            games_not_played: 0,
+            wins_not_played: 0,
+            win_rate_not_played: None,
+        }
+    }
+}
+
+pub fn write_card_csv(path: &Path, impacts: &[CardImpact]) -> Result<(), ExportError> {
+    let mut writer = csv::Writer::from_path(path)?;
+    for impact in impacts {
+        writer.serialize(impact)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn export_card_stats(results: Res<GameResults>, config: Res<SimulationConfig>) {
+    if let Some(path) = &config.card_stats {
+        match write_card_csv(path, &card_impacts(&results.0)) {
+            Ok(()) => info!("Wrote card statistics to {}", path.display()),
+            Err(err) => error!("Failed to write {}: {}", path.display(), err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::sim::CardTally;
+
+    fn tally(played: usize) -> CardTally {
+        CardTally {
+            in_deck: true,
+            played,
+            ..Default::default()
+        }
+    }
+
+    fn result(winner: Side, player: CardTally, enemy: CardTally) -> GameResult {
+        let cards = |tally| HashMap::from([(Arc::from("squire"), tally)]);
+        GameResult {
+            game: 0,
+            seed: 0,
+            winner,
+            first: Side::Player,
+            turns: 1,
+            player_health: 1,
+            enemy_health: 0,
+            player_cards_left: 0,
+            enemy_cards_left: 0,
+            cards: Some([cards(player), cards(enemy)]),
+        }
+    }
+
+    #[test]
+    fn card_in_both_decks_gets_a_row_per_side() {
+        let results = [
+            result(Side::Player, tally(2), tally(0)),
+            result(Side::Player, tally(0), tally(1)),
+            result(Side::Enemy, tally(1), tally(1)),
+        ];
+        let impacts = card_impacts(&results);
+        let [player, enemy] = &impacts[..] else {
+            panic!("expected a row per side, got {:?}", impacts);
+        };
+
+        assert_eq!((player.side, player.played), (Side::Player, 3));
+        assert_eq!((player.games_played, player.wins_played), (2, 1));
+        assert_eq!(player.win_rate_played, Some(0.5));
+        assert_eq!((player.games_not_played, player.wins_not_played), (1, 1));
+        assert_eq!(player.win_rate_not_played, Some(1.0));
+
+        assert_eq!((enemy.side, enemy.played), (Side::Enemy, 2));
+        assert_eq!((enemy.games_played, enemy.wins_played), (2, 1));
+        assert_eq!((enemy.games_not_played, enemy.wins_not_played), (1, 0));
+        assert_eq!(enemy.win_rate_not_played, Some(0.0));
+    }
+
+    #[test]
+    fn games_without_tallies_are_skipped() {
+        let mut untracked = result(Side::Player, tally(1), tally(1));
+        untracked.cards = None;
+        assert!(card_impacts(&[untracked]).is_empty());
+    }
+}
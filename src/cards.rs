@@ -0,0 +1,249 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    decks::DeckList,
+    effects::{Effect, Trigger, TriggeredEffect},
+    ron_asset::{RonAsset, RonAssetLoader},
+};
+
+pub struct CardsPlugin;
+
+impl Plugin for CardsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<CardDatabase>()
+            .init_asset_loader::<RonAssetLoader<CardDatabase>>()
+            .init_asset::<DeckList>()
+            .init_asset_loader::<RonAssetLoader<DeckList>>();
+    }
+}
+
+/// A single card as written in a `.cards.ron` file.
+#[derive(Deserialize, Debug, Clone)]
+pub struct CardDefinition {
+    /// Stable identifier used by deck lists, never shown to players.
+    pub id: String,
+    // Not used by the simulation, but kept so card files stay readable
+    #[allow(dead_code)]
+    pub name: String,
+    #[serde(default)]
+    pub kind: CardKind,
+    /// Energy spent to play the card.
+    pub cost: i32,
+    /// For equipment, what it adds to the unit carrying it.
+    #[serde(default)]
+    pub damage: i32,
+    #[serde(default)]
+    pub health: i32,
+    #[serde(default)]
+    pub keywords: Vec<Keyword>,
+    #[serde(default)]
+    pub effects: Vec<TriggeredEffect<String>>,
+    #[allow(dead_code)]
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct CardDatabase {
+    pub cards: Vec<CardDefinition>,
+}
+
+impl CardDatabase {
+    pub fn get(&self, id: &str) -> Option<&CardDefinition> {
+        self.cards.iter().find(|card| card.id == id)
+    }
+
+    /// Builds a fresh copy of card `id`, along with any tokens it summons.
+    pub fn card(&self, id: &str) -> Option<Card> {
+        let definition = self.get(id)?;
+        let effects = definition
+            .effects
+            .iter()
+            .map(|triggered| {
+                let effect = match &triggered.effect {
+                    Effect::Damage(amount) => Effect::Damage(*amount),
+                    Effect::Draw(cards) => Effect::Draw(*cards),
+                    Effect::Heal(amount) => Effect::Heal(*amount),
+                    Effect::Summon(id) => Effect::Summon(Box::new(self.card(id)?)),
+                };
+                Some(TriggeredEffect {
+                    trigger: triggered.trigger,
+                    effect,
+                })
+            })
+            .collect::<Option<_>>()?;
+        Some(Card {
+            id: definition.id.as_str().into(),
+            kind: definition.kind,
+            cost: definition.cost,
+            damage: definition.damage,
+            health: definition.health,
+            max_health: definition.health,
+            keywords: definition.keywords.clone(),
+            effects,
+            attached: Vec::new(),
+            summoning_sick: false,
+        })
+    }
+
+    /// Fails if `card` summons itself, directly or through its tokens.
+    fn check_summons<'a>(
+        &'a self,
+        card: &'a CardDefinition,
+        chain: &mut Vec<&'a str>,
+    ) -> Result<(), String> {
+        if chain.contains(&card.id.as_str()) {
+            return Err(format!("card `{}` ends up summoning itself", card.id));
+        }
+        chain.push(&card.id);
+        for triggered in &card.effects {
+            if let Effect::Summon(id) = &triggered.effect {
+                let token = self
+                    .get(id)
+                    .ok_or_else(|| format!("card `{}` summons unknown card `{}`", card.id, id))?;
+                if token.kind != CardKind::Unit {
+                    return Err(format!(
+                        "card `{}` summons `{}`, which is not a unit",
+                        card.id, id
+                    ));
+                }
+                self.check_summons(token, chain)?;
+            }
+        }
+        chain.pop();
+        Ok(())
+    }
+}
+
+impl RonAsset for CardDatabase {
+    const EXTENSIONS: &'static [&'static str] = &["cards.ron"];
+
+    fn validate(&self) -> Result<(), String> {
+        for (i, card) in self.cards.iter().enumerate() {
+            if self.cards[..i].iter().any(|other| other.id == card.id) {
+                return Err(format!("duplicate card id `{}`", card.id));
+            }
+            if card.cost < 0 {
+                return Err(format!("card `{}` has a negative cost", card.id));
+            }
+            if card.kind != CardKind::Unit
+                && card
+                    .effects
+                    .iter()
+                    .any(|triggered| triggered.trigger != Trigger::OnPlay)
+            {
+                return Err(format!(
+                    "card `{}` is not a unit, it can only have OnPlay effects",
+                    card.id
+                ));
+            }
+            self.check_summons(card, &mut Vec::new())?;
+        }
+        Ok(())
+    }
+}
+
+/// Asset path of the card database every deck draws its cards from.
+pub const CARD_DATABASE: &str = "cards/base.cards.ron";
+
+#[derive(Resource)]
+pub struct CardDatabaseHandle(pub Handle<CardDatabase>);
+
+/// How a card is played.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CardKind {
+    /// Takes up a lane, attacking and blocking until destroyed.
+    #[default]
+    Unit,
+    /// Resolves its effects and goes straight to the discard pile.
+    Spell,
+    /// Attaches to a unit in play, adding its damage, health and keywords.
+    Equipment,
+}
+
+/// Abilities that change how a card fights.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keyword {
+    /// Takes every hit aimed at its side, before other cards or the deck.
+    Taunt,
+    /// Damage left over after destroying a blocker hits the deck.
+    Piercing,
+    /// Heals its owner by the damage it deals.
+    Lifesteal,
+    /// Heals back to full at the start of its owner's turn.
+    Regenerate,
+    /// Picks off the strongest card it can destroy in any lane, and otherwise
+    /// attacks like any other card.
+    Ranged,
+    /// Attacks on the turn it is played.
+    Haste,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Card {
+    /// Id of the definition this card was built from.
+    pub id: Arc<str>,
+    pub kind: CardKind,
+    pub cost: i32,
+    pub damage: i32,
+    pub health: i32,
+    pub max_health: i32,
+    pub keywords: Vec<Keyword>,
+    pub effects: Vec<TriggeredEffect<Box<Card>>>,
+    /// Equipment this unit carries, discarded along with it.
+    pub attached: Vec<Card>,
+    /// Played this turn and can't attack yet.
+    pub summoning_sick: bool,
+}
+
+impl Card {
+    pub fn has(&self, keyword: Keyword) -> bool {
+        self.keywords.contains(&keyword)
+    }
+
+    /// Adds `equipment`'s stats and keywords to this unit.
+    pub fn equip(&mut self, equipment: Card) {
+        self.damage += equipment.damage;
+        self.health += equipment.health;
+        self.max_health += equipment.health;
+        self.keywords.extend(&equipment.keywords);
+        self.attached.push(equipment);
+    }
+
+    /// Whether any of this card's effects puts a token into play.
+    pub fn summons(&self) -> bool {
+        self.effects
+            .iter()
+            .any(|triggered| matches!(triggered.effect, Effect::Summon(_)))
+    }
+
+    /// Effects this card fires on `trigger`.
+    pub fn effects_on(&self, trigger: Trigger) -> impl Iterator<Item = &Effect<Box<Card>>> + '_ {
+        self.effects
+            .iter()
+            .filter(move |triggered| triggered.trigger == trigger)
+            .map(|triggered| &triggered.effect)
+    }
+}
+
+#[cfg(test)]
+impl Card {
+    /// A free unit with no keywords or effects.
+    pub fn unit(id: &str, damage: i32, health: i32) -> Self {
+        Card {
+            id: id.into(),
+            kind: CardKind::Unit,
+            cost: 0,
+            damage,
+            health,
+            max_health: health,
+            keywords: Vec::new(),
+            effects: Vec::new(),
+            attached: Vec::new(),
+            summoning_sick: false,
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use std::{path::PathBuf, time::Duration};
+
+use bevy_turborand::prelude::*;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::{
+    decks::DeckSelection,
+    game::{SimulationConfig, StepMode},
+    rules::RulesSelection,
+    strategy::{MctsBudget, StrategyKind},
+    tournament::TournamentSettings,
+};
+
+#[derive(Parser)]
+#[command(about = "Plays card games against each other to measure deck balance")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Run games without a window and print the results
+    Simulate(SimulateArgs),
+    /// Watch games play out side by side
+    View(ViewArgs),
+    /// Step through a single game, one phase per press of space
+    Play(MatchArgs),
+    /// Watch a recorded game again, with play, pause and step controls
+    Replay(ReplayArgs),
+    /// Play every deck in a folder against every other deck from both seats
+    Tournament(TournamentArgs),
+    /// Play a tournament and update the Elo ratings of its entrants in a
+    /// ladder file
+    Ladder(LadderArgs),
+}
+
+/// Settings shared by every way of running games.
+#[derive(Args)]
+pub struct MatchArgs {
+    /// Seed for the random number generator, random if not given
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Deck list used by the player, relative to the assets folder
+    #[arg(long, default_value = "decks/aggro.deck.ron")]
+    pub player_deck: String,
+    /// Deck list used by the enemy, relative to the assets folder
+    #[arg(long, default_value = "decks/control.deck.ron")]
+    pub enemy_deck: String,
+    #[command(flatten)]
+    pub rules: RulesArgs,
+    #[command(flatten)]
+    pub ai: AiArgs,
+}
+
+#[derive(Args)]
+pub struct RulesArgs {
+    /// Rule set to play by, relative to the assets folder
+    #[arg(long, default_value = "rules/standard.rules.ron")]
+    pub rules: String,
+    /// Number of turns before a game is called a draw, overriding the rule set
+    #[arg(long)]
+    pub turn_limit: Option<usize>,
+    /// Number of lanes on each side of the board, overriding the rule set
+    #[arg(long)]
+    pub lanes: Option<usize>,
+}
+
+#[derive(Args)]
+pub struct AiArgs {
+    /// AI playing the player's deck
+    #[arg(long, value_enum, default_value_t = StrategyKind::Random)]
+    pub player_ai: StrategyKind,
+    /// AI playing the enemy's deck
+    #[arg(long, value_enum, default_value_t = StrategyKind::Random)]
+    pub enemy_ai: StrategyKind,
+    #[command(flatten)]
+    pub mcts: MctsArgs,
+}
+
+#[derive(Args)]
+pub struct MctsArgs {
+    /// Rollouts the mcts AI runs per move
+    #[arg(long, default_value_t = 200)]
+    pub mcts_iterations: usize,
+    /// Stop the mcts AI after this many milliseconds per move, even if it has
+    /// not run every rollout. Makes results depend on machine speed
+    #[arg(long)]
+    pub mcts_millis: Option<u64>,
+}
+
+#[derive(Args)]
+pub struct SimulateArgs {
+    /// Number of games to play
+    #[arg(short = 'n', long, default_value_t = 100)]
+    pub games: usize,
+    /// How the results are printed
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+    /// Write per-game results to this CSV file
+    #[arg(long)]
+    pub csv: Option<PathBuf>,
+    /// Write per-game results to this JSON file
+    #[arg(long)]
+    pub json: Option<PathBuf>,
+    /// Write a replay of every game into this folder
+    #[arg(long)]
+    pub replays: Option<PathBuf>,
+    /// Write per-card statistics to this CSV file
+    #[arg(long)]
+    pub card_stats: Option<PathBuf>,
+    #[command(flatten)]
+    pub game: MatchArgs,
+}
+
+#[derive(Args)]
+pub struct ViewArgs {
+    #[command(flatten)]
+    pub simulate: SimulateArgs,
+    /// Phases every game advances each frame
+    #[arg(long, default_value_t = 1, conflicts_with = "until_halted")]
+    pub steps_per_frame: usize,
+    /// Keep stepping each frame until every game is over or the frame budget
+    /// runs out
+    #[arg(long)]
+    pub until_halted: bool,
+    /// Milliseconds each frame may spend simulating, at least one phase is
+    /// always played
+    #[arg(long, default_value_t = 12)]
+    pub frame_budget_ms: u64,
+}
+
+#[derive(Args)]
+pub struct ReplayArgs {
+    /// Replay file written by `--replays`
+    pub file: PathBuf,
+    /// Milliseconds between phases while the replay is playing
+    #[arg(long, default_value_t = 500)]
+    pub step_ms: u64,
+}
+
+#[derive(Args)]
+pub struct TournamentArgs {
+    /// Seed for the random number generator, random if not given
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Folder of deck lists to play against each other, relative to the
+    /// assets folder
+    #[arg(long, default_value = "decks")]
+    pub decks: String,
+    /// Games each entrant plays against each other entrant from each seat
+    #[arg(short = 'n', long, default_value_t = 100)]
+    pub games: usize,
+    /// AIs every deck is entered with, each deck and AI pair playing as its
+    /// own entrant
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "random")]
+    pub ai: Vec<StrategyKind>,
+    /// Write the matchup matrix to this CSV file
+    #[arg(long)]
+    pub csv: Option<PathBuf>,
+    /// Show the matchup matrix as a heatmap in a window instead of exiting
+    #[arg(long)]
+    pub view: bool,
+    #[command(flatten)]
+    pub rules: RulesArgs,
+    #[command(flatten)]
+    pub mcts: MctsArgs,
+}
+
+#[derive(Args)]
+pub struct LadderArgs {
+    #[command(flatten)]
+    pub tournament: TournamentArgs,
+    /// Ladder file to read ratings from and write them back to, started
+    /// afresh if it doesn't exist
+    #[arg(long, default_value = "ladder.ron")]
+    pub ladder: PathBuf,
+    /// Most rating points a single game can move
+    #[arg(long, default_value_t = 16.0)]
+    pub k_factor: f64,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl MatchArgs {
+    pub fn deck_selection(&self) -> DeckSelection {
+        DeckSelection {
+            player: self.player_deck.clone(),
+            enemy: self.enemy_deck.clone(),
+        }
+    }
+
+    pub fn rules_selection(&self) -> RulesSelection {
+        self.rules.selection()
+    }
+
+    pub fn config(&self, games: usize, step_mode: StepMode) -> SimulationConfig {
+        self.ai.config(self.seed, games, step_mode)
+    }
+}
+
+impl RulesArgs {
+    pub fn selection(&self) -> RulesSelection {
+        RulesSelection {
+            path: self.rules.clone(),
+            turn_limit: self.turn_limit,
+            lanes: self.lanes,
+        }
+    }
+}
+
+impl AiArgs {
+    pub fn config(&self, seed: Option<u64>, games: usize, step_mode: StepMode) -> SimulationConfig {
+        SimulationConfig {
+            games,
+            seed: seed.unwrap_or_else(|| Rng::new().gen_u64()),
+            format: OutputFormat::Text,
+            step_mode,
+            player_strategy: self.player_ai,
+            enemy_strategy: self.enemy_ai,
+            mcts: self.mcts.budget(),
+            csv: None,
+            json: None,
+            replays: None,
+            card_stats: None,
+        }
+    }
+}
+
+impl MctsArgs {
+    pub fn budget(&self) -> MctsBudget {
+        MctsBudget {
+            iterations: self.mcts_iterations,
+            time_budget: self.mcts_millis.map(Duration::from_millis),
+        }
+    }
+}
+
+impl SimulateArgs {
+    pub fn config(&self, step_mode: StepMode) -> SimulationConfig {
+        SimulationConfig {
+            format: self.format,
+            csv: self.csv.clone(),
+            json: self.json.clone(),
+            replays: self.replays.clone(),
+            card_stats: self.card_stats.clone(),
+            ..self.game.config(self.games, step_mode)
+        }
+    }
+}
+
+impl TournamentArgs {
+    pub fn config(&self) -> SimulationConfig {
+        SimulationConfig {
+            games: self.games,
+            seed: self.seed.unwrap_or_else(|| Rng::new().gen_u64()),
+            format: OutputFormat::Text,
+            step_mode: StepMode::Batch,
+            // Entrants bring their own AIs
+            player_strategy: StrategyKind::Random,
+            enemy_strategy: StrategyKind::Random,
+            mcts: self.mcts.budget(),
+            csv: None,
+            json: None,
+            replays: None,
+            card_stats: None,
+        }
+    }
+
+    pub fn settings(&self) -> TournamentSettings {
+        TournamentSettings {
+            folder: self.decks.clone(),
+            ais: self.ai.clone(),
+            csv: self.csv.clone(),
+        }
+    }
+}
+
+impl ViewArgs {
+    pub fn config(&self) -> SimulationConfig {
+        self.simulate.config(StepMode::EveryFrame {
+            steps: (!self.until_halted).then_some(self.steps_per_frame),
+            budget: Duration::from_millis(self.frame_budget_ms),
+        })
+    }
+}
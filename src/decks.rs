@@ -0,0 +1,92 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    cards::{Card, CardDatabase},
+    ron_asset::RonAsset,
+    rules::RuleSet,
+};
+
+/// A named deck as written in a `.deck.ron` file.
+#[derive(Asset, TypePath, Serialize, Deserialize, Clone, Debug)]
+pub struct DeckList {
+    pub name: String,
+    /// Overrides the rule set's starting health for this deck.
+    #[serde(default)]
+    pub health: Option<i32>,
+    pub cards: Vec<DeckEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DeckEntry {
+    /// Id of a card in the card database.
+    pub id: String,
+    pub count: usize,
+}
+
+impl RonAsset for DeckList {
+    const EXTENSIONS: &'static [&'static str] = &["deck.ron"];
+
+    fn validate(&self) -> Result<(), String> {
+        if self.health.is_some_and(|health| health <= 0) {
+            return Err(format!("deck `{}` must have positive health", self.name));
+        }
+        Ok(())
+    }
+}
+
+/// Asset paths of the deck lists each side plays with.
+#[derive(Resource, Clone)]
+pub struct DeckSelection {
+    pub player: String,
+    pub enemy: String,
+}
+
+#[derive(Resource)]
+pub struct DeckHandles {
+    pub player: Handle<DeckList>,
+    pub enemy: Handle<DeckList>,
+}
+
+/// The decks built from the selected deck lists, ready to be played.
+#[derive(Resource)]
+pub struct MatchDecks {
+    pub player: Deck,
+    pub enemy: Deck,
+}
+
+#[derive(Debug, Error)]
+pub enum DeckError {
+    #[error("Deck `{deck}` uses unknown card `{card}`")]
+    UnknownCard { deck: String, card: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Deck {
+    pub cards: Vec<Card>,
+    pub health: i32,
+}
+
+impl Deck {
+    pub fn from_list(
+        list: &DeckList,
+        database: &CardDatabase,
+        rules: &RuleSet,
+    ) -> Result<Self, DeckError> {
+        let mut cards = Vec::new();
+        for entry in &list.cards {
+            let card = database
+                .card(&entry.id)
+                .ok_or_else(|| DeckError::UnknownCard {
+                    deck: list.name.clone(),
+                    card: entry.id.clone(),
+                })?;
+            cards.extend(std::iter::repeat_n(card, entry.count));
+        }
+        Ok(Deck {
+            cards,
+            health: list.health.unwrap_or(rules.starting_health),
+        })
+    }
+}
@@ -0,0 +1,179 @@
+//! Triggered card effects and the engine that resolves them.
+//!
+//! Rules code only queues effects on the player whose card triggered them,
+//! `resolve_effects` then applies them once both players are at hand.
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cards::{Card, Keyword},
+    rules::RuleSet,
+    sim::{GameEvent, PlayerState},
+};
+
+/// When a card's effect fires.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// The card is played from hand.
+    OnPlay,
+    /// The card is destroyed in combat.
+    OnDeath,
+    /// Its owner's turn starts while the card is in play.
+    StartOfTurn,
+    /// Its owner's turn ends while the card is in play.
+    EndOfTurn,
+}
+
+/// Something a card does when its trigger fires.
+///
+/// `T` names the card a `Summon` puts into play: its id in card files, the
+/// card itself once a deck has been built.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Effect<T> {
+    /// Damages the opposing deck.
+    Damage(i32),
+    /// Draws cards for the owner.
+    Draw(usize),
+    /// Heals the owner's deck.
+    Heal(i32),
+    /// Puts a token into the card's lane, if it is empty.
+    Summon(T),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TriggeredEffect<T> {
+    pub trigger: Trigger,
+    pub effect: Effect<T>,
+}
+
+/// An effect that has triggered but not resolved yet.
+#[derive(Debug, Clone)]
+pub struct PendingEffect {
+    /// Id of the card that triggered it.
+    pub source: Arc<str>,
+    /// Lane of the card that triggered it.
+    pub lane: usize,
+    pub effect: Effect<Box<Card>>,
+}
+
+/// Resolves every effect queued on either player, the active player's first.
+pub fn resolve_effects(
+    active: &mut PlayerState,
+    other: &mut PlayerState,
+    rules: &RuleSet,
+    events: &mut Vec<GameEvent>,
+) {
+    resolve_queue(active, other, rules, events);
+    resolve_queue(other, active, rules, events);
+}
+
+fn resolve_queue(
+    owner: &mut PlayerState,
+    opponent: &mut PlayerState,
+    rules: &RuleSet,
+    events: &mut Vec<GameEvent>,
+) {
+    for pending in std::mem::take(&mut owner.pending) {
+        match pending.effect {
+            Effect::Damage(amount) => {
+                opponent.health -= amount;
+                events.push(GameEvent::DirectDamage {
+                    side: opponent.side,
+                    lane: pending.lane,
+                    source: pending.source,
+                    damage: amount,
+                    health: opponent.health,
+                });
+            }
+            Effect::Draw(cards) => {
+                for _ in 0..cards {
+                    owner.draw(rules);
+                }
+            }
+            Effect::Heal(amount) => owner.health += amount,
+            Effect::Summon(mut card) => {
+                let lane = &mut owner.area.cards[pending.lane];
+                if lane.is_none() {
+                    card.summoning_sick = !card.has(Keyword::Haste);
+                    *lane = Some(*card);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_turborand::prelude::*;
+
+    use super::*;
+    use crate::{
+        cards::CardKind,
+        sim::{attack, GamePhase, GameState, Seat, Side},
+        strategy::GreedyStrategy,
+    };
+
+    fn with_effect(trigger: Trigger, effect: Effect<Box<Card>>, card: Card) -> Card {
+        Card {
+            effects: vec![TriggeredEffect { trigger, effect }],
+            ..card
+        }
+    }
+
+    #[test]
+    fn on_death_summon_fills_the_freed_lane() {
+        let rules = RuleSet::default();
+        let egg = with_effect(
+            Trigger::OnDeath,
+            Effect::Summon(Box::new(Card::unit("hatchling", 1, 1))),
+            Card::unit("egg", 0, 1),
+        );
+        let mut attacker =
+            PlayerState::in_play(Side::Player, 10, vec![None, Some(Card::unit("a", 2, 1))]);
+        let mut defender = PlayerState::in_play(Side::Enemy, 10, vec![None, Some(egg)]);
+        let mut events = Vec::new();
+        attack(&mut attacker, &mut defender, &rules, &mut events);
+        assert!(defender.area.cards[1].is_none());
+        resolve_effects(&mut attacker, &mut defender, &rules, &mut events);
+        let token = defender.area.cards[1].as_ref().unwrap();
+        assert_eq!(&*token.id, "hatchling");
+        assert!(token.summoning_sick);
+        assert_eq!(&*defender.discard[0].id, "egg");
+        assert!(defender.area.cards[0].is_none());
+    }
+
+    #[test]
+    fn effect_damage_halts_the_game_before_the_attack() {
+        let rules = RuleSet::default();
+        let fireball = Card {
+            kind: CardKind::Spell,
+            ..with_effect(
+                Trigger::OnPlay,
+                Effect::Damage(2),
+                Card::unit("fireball", 0, 0),
+            )
+        };
+        let seat = |state: PlayerState| Seat {
+            state,
+            strategy: Box::new(GreedyStrategy),
+            rng: RngComponent::with_seed(0),
+        };
+        let mut player = PlayerState::in_play(Side::Player, 10, vec![None]);
+        player.hand.push(fireball);
+        let enemy = PlayerState::in_play(Side::Enemy, 2, vec![None]);
+        let mut game = GameState::new(seat(player), seat(enemy), rules, Side::Player);
+        game.step();
+        assert_eq!(game.phase, GamePhase::Halt);
+        assert_eq!(game.side, Side::Player);
+        assert_eq!(game.player(Side::Enemy).health, 0);
+        assert!(matches!(
+            game.events.last(),
+            Some(GameEvent::GameEnded {
+                winner: Side::Player,
+                turns: 0
+            })
+        ));
+    }
+}
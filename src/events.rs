@@ -0,0 +1,183 @@
+//! Bevy events for what happens in games stepped as entities, so other
+//! systems can follow a game without re-deriving its state.
+//!
+//! Batch runs play games outside the ECS and send none of these.
+
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+use crate::{
+    game::Game,
+    sim::{GameEvent, Side},
+};
+
+pub struct GameEventsPlugin;
+
+impl Plugin for GameEventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CardPlayed>()
+            .add_event::<CardBlocked>()
+            .add_event::<CardDestroyed>()
+            .add_event::<DirectDamage>()
+            .add_event::<GameEnded>();
+    }
+}
+
+#[derive(Event, Clone, Debug)]
+pub struct CardPlayed {
+    pub game: usize,
+    pub side: Side,
+    pub lane: usize,
+    pub card: Arc<str>,
+}
+
+/// A card in play took a hit from `attacker`.
+#[derive(Event, Clone, Debug)]
+pub struct CardBlocked {
+    pub game: usize,
+    pub side: Side,
+    pub lane: usize,
+    pub card: Arc<str>,
+    pub attacker: Arc<str>,
+    pub damage: i32,
+    /// Health the card has left, a `CardDestroyed` follows if it died.
+    pub health: i32,
+}
+
+#[derive(Event, Clone, Debug)]
+pub struct CardDestroyed {
+    pub game: usize,
+    pub side: Side,
+    pub lane: usize,
+    pub card: Arc<str>,
+}
+
+/// The deck of `side` took damage from `source`'s attack or effect.
+#[derive(Event, Clone, Debug)]
+pub struct DirectDamage {
+    pub game: usize,
+    pub side: Side,
+    /// Lane of `source`, on the other side.
+    pub lane: usize,
+    pub source: Arc<str>,
+    pub damage: i32,
+    pub health: i32,
+}
+
+#[derive(Event, Clone, Debug)]
+pub struct GameEnded {
+    pub game: usize,
+    pub winner: Side,
+    pub turns: usize,
+}
+
+/// Drains every game's event log into the matching Bevy events.
+pub fn send_game_events(
+    mut games: Query<&mut Game>,
+    mut played: EventWriter<CardPlayed>,
+    mut blocked: EventWriter<CardBlocked>,
+    mut destroyed: EventWriter<CardDestroyed>,
+    mut direct: EventWriter<DirectDamage>,
+    mut ended: EventWriter<GameEnded>,
+) {
+    for mut game in &mut games {
+        let id = game.id;
+        for event in std::mem::take(&mut game.state.events) {
+            match event {
+                GameEvent::CardPlayed { side, lane, card } => played.send(CardPlayed {
+                    game: id,
+                    side,
+                    lane,
+                    card,
+                }),
+                GameEvent::CardBlocked {
+                    side,
+                    lane,
+                    card,
+                    attacker,
+                    damage,
+                    health,
+                } => blocked.send(CardBlocked {
+                    game: id,
+                    side,
+                    lane,
+                    card,
+                    attacker,
+                    damage,
+                    health,
+                }),
+                GameEvent::CardDestroyed { side, lane, card } => destroyed.send(CardDestroyed {
+                    game: id,
+                    side,
+                    lane,
+                    card,
+                }),
+                GameEvent::DirectDamage {
+                    side,
+                    lane,
+                    source,
+                    damage,
+                    health,
+                } => direct.send(DirectDamage {
+                    game: id,
+                    side,
+                    lane,
+                    source,
+                    damage,
+                    health,
+                }),
+                GameEvent::GameEnded { winner, turns } => ended.send(GameEnded {
+                    game: id,
+                    winner,
+                    turns,
+                }),
+            }
+        }
+    }
+}
+
+pub fn log_game_events(
+    mut played: EventReader<CardPlayed>,
+    mut blocked: EventReader<CardBlocked>,
+    mut destroyed: EventReader<CardDestroyed>,
+    mut direct: EventReader<DirectDamage>,
+    mut ended: EventReader<GameEnded>,
+) {
+    for event in played.read() {
+        info!(
+            "Game {}: {:?} played {} at {}",
+            event.game, event.side, event.card, event.lane
+        );
+    }
+    for event in blocked.read() {
+        info!(
+            "Game {}: {:?} {} at {} blocked {} and took {} damage, {} health left",
+            event.game,
+            event.side,
+            event.card,
+            event.lane,
+            event.attacker,
+            event.damage,
+            event.health
+        );
+    }
+    for event in destroyed.read() {
+        info!(
+            "Game {}: {:?} {} at {} was destroyed",
+            event.game, event.side, event.card, event.lane
+        );
+    }
+    for event in direct.read() {
+        info!(
+            "Game {}: {} at {} hit {:?} directly for {}, {} health left",
+            event.game, event.source, event.lane, event.side, event.damage, event.health
+        );
+    }
+    for event in ended.read() {
+        info!(
+            "Game {}: over after {} turns, result {:?}",
+            event.game, event.turns, event.winner
+        );
+    }
+}
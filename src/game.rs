@@ -0,0 +1,366 @@
+use bevy::{
+    asset::RecursiveDependencyLoadState,
+    prelude::*,
+    utils::{Duration, Instant},
+};
+use bevy_turborand::prelude::*;
+
+use std::path::PathBuf;
+
+use crate::{
+    batch::run_batch_games,
+    card_stats::export_card_stats,
+    cards::{CardDatabase, CardDatabaseHandle, CardsPlugin, CARD_DATABASE},
+    cli::OutputFormat,
+    decks::{Deck, DeckHandles, DeckList, DeckSelection, MatchDecks},
+    events::{log_game_events, send_game_events, GameEventsPlugin},
+    replay::save_replays,
+    results::{export_results, print_win_rates, GameResult, GameResults},
+    ron_asset::RonAsset,
+    rules::{RuleSet, RulesHandle, RulesPlugin, RulesSelection},
+    seed::derive_seed,
+    sim::{GameState, Side},
+    strategy::{MctsBudget, StrategyKind},
+};
+
+/// Everything needed to load decks and play games out, without any rendering.
+pub struct SimulationPlugin {
+    pub config: SimulationConfig,
+    pub decks: DeckSelection,
+    pub rules: RulesSelection,
+}
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            RngPlugin::new().with_rng_seed(self.config.seed),
+            CardsPlugin,
+            RulesPlugin,
+            GameEventsPlugin,
+        ))
+        .add_state::<AppState>()
+        .insert_resource(self.config.clone())
+        .insert_resource(self.decks.clone())
+        .insert_resource(self.rules.clone())
+        .add_systems(Startup, (print_seed, load_cards))
+        .add_systems(Update, wait_for_cards.run_if(in_state(AppState::Loading)))
+        .add_systems(
+            OnEnter(AppState::Simulating),
+            (
+                spawn_decks.run_if(not(batched)),
+                run_batch_games.run_if(batched),
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                simulate_games.run_if(step_requested),
+                send_game_events,
+                (log_games.run_if(step_requested), log_game_events).run_if(stepping_by_hand),
+                collect_results.run_if(all_games_halted),
+            )
+                .chain()
+                .run_if(in_state(AppState::Simulating).and_then(not(batched))),
+        )
+        .add_systems(
+            OnEnter(AppState::Finished),
+            (
+                print_win_rates,
+                export_results,
+                export_card_stats,
+                save_replays.run_if(not(batched)),
+            ),
+        );
+    }
+}
+
+#[derive(Resource, Clone)]
+pub struct SimulationConfig {
+    pub games: usize,
+    /// Master seed every game's randomness is derived from.
+    pub seed: u64,
+    pub format: OutputFormat,
+    pub step_mode: StepMode,
+    pub player_strategy: StrategyKind,
+    pub enemy_strategy: StrategyKind,
+    pub mcts: MctsBudget,
+    /// Where to write per-game results, if anywhere.
+    pub csv: Option<PathBuf>,
+    pub json: Option<PathBuf>,
+    /// Folder to write a replay of every game into, if any.
+    pub replays: Option<PathBuf>,
+    /// Where to write per-card statistics, if anywhere.
+    pub card_stats: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StepMode {
+    /// Every game advances up to `steps` phases each frame, or until it
+    /// halts if `None`, stopping early once `budget` has been spent.
+    EveryFrame {
+        steps: Option<usize>,
+        budget: Duration,
+    },
+    /// Games only advance when space is pressed.
+    OnKeyPress,
+    /// Games are played to the end in parallel, without entities.
+    Batch,
+}
+
+impl SimulationConfig {
+    /// Sets up game `id` with the given AIs in the player's and enemy's
+    /// seats, returning the seed it was derived from.
+    pub fn new_game(
+        &self,
+        id: usize,
+        decks: &MatchDecks,
+        strategies: [StrategyKind; 2],
+        rules: &RuleSet,
+    ) -> (u64, GameState) {
+        let seed = derive_seed(self.seed, id as u64);
+        let strategies = strategies.map(|kind| kind.build(self.mcts));
+        let mut state = GameState::deal(seed, decks, strategies, rules);
+        if self.card_stats.is_some() {
+            state.track_cards();
+        }
+        (seed, state)
+    }
+}
+
+pub fn print_seed(config: Res<SimulationConfig>) {
+    info!("Seed: {}", config.seed);
+}
+
+fn step_requested(config: Res<SimulationConfig>, keys: Option<Res<Input<KeyCode>>>) -> bool {
+    match config.step_mode {
+        StepMode::EveryFrame { .. } | StepMode::Batch => true,
+        StepMode::OnKeyPress => keys.is_some_and(|keys| keys.just_pressed(KeyCode::Space)),
+    }
+}
+
+fn batched(config: Res<SimulationConfig>) -> bool {
+    config.step_mode == StepMode::Batch
+}
+
+fn stepping_by_hand(config: Res<SimulationConfig>) -> bool {
+    config.step_mode == StepMode::OnKeyPress
+}
+
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AppState {
+    #[default]
+    Loading,
+    Simulating,
+    /// Every game has halted and the results have been reported.
+    Finished,
+}
+
+#[derive(Component)]
+pub struct Game {
+    pub id: usize,
+    /// Seed this game's decks were shuffled with, derived from the master seed.
+    pub seed: u64,
+    pub state: GameState,
+}
+
+fn all_games_halted(games: Query<&Game>) -> bool {
+    games.iter().all(|game| game.state.is_over())
+}
+
+pub fn log_games(games: Query<&Game>) {
+    for game in &games {
+        let state = &game.state;
+        info!(
+            "Turn {} ({:?} {:?}): player health {}, enemy health {}",
+            state.turn_count,
+            state.side,
+            state.phase,
+            state.player(Side::Player).health,
+            state.player(Side::Enemy).health
+        );
+    }
+}
+
+fn collect_results(
+    mut commands: Commands,
+    games: Query<&Game>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let mut results: Vec<GameResult> = games
+        .iter()
+        .map(|game| game.state.result(game.id, game.seed))
+        .collect();
+    results.sort_by_key(|result| result.game);
+    commands.insert_resource(GameResults(results));
+    next_state.set(AppState::Finished);
+}
+
+fn simulate_games(mut games: Query<&mut Game>, config: Res<SimulationConfig>) {
+    let (steps, budget) = match config.step_mode {
+        StepMode::EveryFrame { steps, budget } => (steps, Some(budget)),
+        StepMode::OnKeyPress | StepMode::Batch => (Some(1), None),
+    };
+    let start = Instant::now();
+    let mut step = 0;
+    // Step every game once per round so they all keep pace when the budget
+    // cuts a frame short
+    loop {
+        let mut running = false;
+        for mut game in &mut games {
+            if !game.state.is_over() {
+                game.state.step();
+                running = true;
+            }
+        }
+        step += 1;
+        if !running
+            || steps.is_some_and(|steps| step >= steps)
+            || budget.is_some_and(|budget| start.elapsed() >= budget)
+        {
+            break;
+        }
+    }
+}
+
+fn load_cards(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    selection: Res<DeckSelection>,
+    rules: Res<RulesSelection>,
+) {
+    commands.insert_resource(CardDatabaseHandle(asset_server.load(CARD_DATABASE)));
+    commands.insert_resource(DeckHandles {
+        player: asset_server.load(&selection.player),
+        enemy: asset_server.load(&selection.enemy),
+    });
+    commands.insert_resource(RulesHandle(asset_server.load(&rules.path)));
+}
+
+fn wait_for_cards(
+    mut commands: Commands,
+    database: Res<CardDatabaseHandle>,
+    databases: Res<Assets<CardDatabase>>,
+    decks: Res<DeckHandles>,
+    deck_lists: Res<Assets<DeckList>>,
+    rules: Res<RulesHandle>,
+    rule_sets: Res<Assets<RuleSet>>,
+    selection: Res<RulesSelection>,
+    asset_server: Res<AssetServer>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut exit: EventWriter<AppFailed>,
+) {
+    let handles = [
+        database.0.clone().untyped(),
+        decks.player.clone().untyped(),
+        decks.enemy.clone().untyped(),
+    ];
+    let Some(rules) = loaded_rules(
+        &asset_server,
+        &handles,
+        &rules,
+        &rule_sets,
+        &selection,
+        &mut exit,
+    ) else {
+        return;
+    };
+
+    let database = databases.get(&database.0).unwrap();
+    let build = |handle: &Handle<DeckList>| {
+        let list = deck_lists.get(handle).unwrap();
+        Deck::from_list(list, database, &rules)
+    };
+    match (build(&decks.player), build(&decks.enemy)) {
+        (Ok(player), Ok(enemy)) => {
+            commands.insert_resource(MatchDecks { player, enemy });
+            commands.insert_resource(rules);
+            next_state.set(AppState::Simulating);
+        }
+        (Err(err), _) | (_, Err(err)) => {
+            error!("{}", err);
+            exit.send(AppFailed);
+        }
+    }
+}
+
+/// Sent instead of `AppExit` when the app has to stop because something went
+/// wrong, so the process exits with an error code.
+#[derive(Event)]
+pub struct AppFailed;
+
+/// Waits for `handles` and the rules to load, then applies the command line
+/// overrides to the rules.
+///
+/// Returns `None` until everything has loaded, or for good once anything
+/// has failed to or the rules turn out invalid, which is logged and stops
+/// the app.
+pub fn loaded_rules(
+    asset_server: &AssetServer,
+    handles: &[UntypedHandle],
+    rules: &RulesHandle,
+    rule_sets: &Assets<RuleSet>,
+    selection: &RulesSelection,
+    exit: &mut EventWriter<AppFailed>,
+) -> Option<RuleSet> {
+    let rules_handle = [rules.0.clone().untyped()];
+    match all_loaded(asset_server, &[handles, &rules_handle].concat()) {
+        Loading::Done => {}
+        Loading::Pending => return None,
+        Loading::Failed => {
+            exit.send(AppFailed);
+            return None;
+        }
+    }
+
+    // Command line overrides skip the checks the loader ran on the file
+    let rules = selection.apply(rule_sets.get(&rules.0).unwrap());
+    if let Err(err) = rules.validate() {
+        error!("Invalid rules: {}", err);
+        exit.send(AppFailed);
+        return None;
+    }
+    Some(rules)
+}
+
+/// How far a set of assets has got with loading.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Loading {
+    Pending,
+    Done,
+    /// At least one of them failed, and has been logged.
+    Failed,
+}
+
+/// Whether every handle has loaded along with its dependencies, logging any
+/// that failed to.
+fn all_loaded(asset_server: &AssetServer, handles: &[UntypedHandle]) -> Loading {
+    let mut loading = Loading::Done;
+    for handle in handles {
+        match asset_server.get_recursive_dependency_load_state(handle.id()) {
+            Some(RecursiveDependencyLoadState::Loaded) => {}
+            Some(RecursiveDependencyLoadState::Failed) => {
+                error!("Failed to load {:?}", handle.path());
+                loading = Loading::Failed;
+            }
+            _ => {
+                if loading == Loading::Done {
+                    loading = Loading::Pending;
+                }
+            }
+        }
+    }
+    loading
+}
+
+fn spawn_decks(
+    mut commands: Commands,
+    decks: Res<MatchDecks>,
+    rules: Res<RuleSet>,
+    config: Res<SimulationConfig>,
+) {
+    for id in 0..config.games {
+        let strategies = [config.player_strategy, config.enemy_strategy];
+        let (seed, state) = config.new_game(id, &decks, strategies, &rules);
+        commands.spawn(Game { id, seed, state });
+    }
+}
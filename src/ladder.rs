@@ -0,0 +1,267 @@
+//! Elo ratings for tournament entrants, kept in a ladder file so they carry
+//! over from run to run as decks are added and changed.
+
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    game::{AppFailed, AppState},
+    sim::Side,
+    strategy::StrategyKind,
+    tournament::{Entrant, TournamentResults},
+};
+
+/// Rating of an entrant the ladder hasn't seen before.
+const INITIAL_RATING: f64 = 1500.0;
+
+/// Reads the ladder before a tournament and writes it back with the
+/// tournament's games rated once it has finished.
+pub struct LadderPlugin {
+    pub settings: LadderSettings,
+}
+
+impl Plugin for LadderPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.settings.clone())
+            .add_systems(Startup, load_ladder)
+            .add_systems(OnEnter(AppState::Finished), update_ladder);
+    }
+}
+
+#[derive(Resource, Clone)]
+pub struct LadderSettings {
+    pub path: PathBuf,
+    /// Most rating points a single game can move.
+    pub k_factor: f64,
+}
+
+/// Every entrant ever rated, as written in a ladder file.
+#[derive(Resource, Serialize, Deserialize, Default, Debug)]
+pub struct Ladder {
+    pub entries: Vec<LadderEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LadderEntry {
+    pub deck: String,
+    pub ai: StrategyKind,
+    pub rating: f64,
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+}
+
+#[derive(Debug, Error)]
+pub enum LadderError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Write(#[from] ron::Error),
+    #[error("{0}")]
+    Read(#[from] ron::error::SpannedError),
+}
+
+impl Ladder {
+    /// Reads the ladder at `path`, or starts an empty one if there is no
+    /// file there yet.
+    pub fn load(path: &Path) -> Result<Self, LadderError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(ron::from_str(&text)?),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Ladder::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), LadderError> {
+        let text = ron::ser::to_string_pretty(self, PrettyConfig::new().depth_limit(2))?;
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// Index of the entry for `entrant`, adding it if it is new.
+    fn entry(&mut self, entrant: &Entrant) -> usize {
+        let found = self
+            .entries
+            .iter()
+            .position(|entry| entry.deck == entrant.deck && entry.ai == entrant.ai);
+        found.unwrap_or_else(|| {
+            self.entries.push(LadderEntry {
+                deck: entrant.deck.clone(),
+                ai: entrant.ai,
+                rating: INITIAL_RATING,
+                wins: 0,
+                losses: 0,
+                draws: 0,
+            });
+            self.entries.len() - 1
+        })
+    }
+
+    /// Rates every game of a tournament in the order it lists them.
+    pub fn record(&mut self, results: &TournamentResults, k_factor: f64) {
+        let entries: Vec<usize> = results
+            .entrants
+            .iter()
+            .map(|entrant| self.entry(entrant))
+            .collect();
+        for outcome in &results.games {
+            let (player, enemy) = (entries[outcome.player], entries[outcome.enemy]);
+            let score = match outcome.winner {
+                Side::Player => {
+                    self.entries[player].wins += 1;
+                    self.entries[enemy].losses += 1;
+                    1.0
+                }
+                Side::Enemy => {
+                    self.entries[player].losses += 1;
+                    self.entries[enemy].wins += 1;
+                    0.0
+                }
+                Side::Draw => {
+                    self.entries[player].draws += 1;
+                    self.entries[enemy].draws += 1;
+                    0.5
+                }
+            };
+            let expected = expected_score(self.entries[player].rating, self.entries[enemy].rating);
+            let change = k_factor * (score - expected);
+            self.entries[player].rating += change;
+            self.entries[enemy].rating -= change;
+        }
+        self.entries.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+    }
+}
+
+/// Chance of a player rated `rating` beating one rated `opponent`, counting
+/// a draw as half a win.
+fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+fn load_ladder(
+    mut commands: Commands,
+    settings: Res<LadderSettings>,
+    mut exit: EventWriter<AppFailed>,
+) {
+    // Read the ladder up front so a broken file doesn't waste a tournament
+    match Ladder::load(&settings.path) {
+        Ok(ladder) => commands.insert_resource(ladder),
+        Err(err) => {
+            error!("Failed to read {}: {}", settings.path.display(), err);
+            exit.send(AppFailed);
+        }
+    }
+}
+
+fn update_ladder(
+    mut ladder: ResMut<Ladder>,
+    results: Res<TournamentResults>,
+    settings: Res<LadderSettings>,
+) {
+    ladder.record(&results, settings.k_factor);
+    for (rank, entry) in ladder.entries.iter().enumerate() {
+        info!(
+            "{}. {} ({:?}): {:.0}, {} wins, {} losses, {} draws",
+            rank + 1,
+            entry.deck,
+            entry.ai,
+            entry.rating,
+            entry.wins,
+            entry.losses,
+            entry.draws
+        );
+    }
+    match ladder.save(&settings.path) {
+        Ok(()) => info!("Wrote ladder to {}", settings.path.display()),
+        Err(err) => error!("Failed to write {}: {}", settings.path.display(), err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tournament::Outcome;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "{actual} is not close to {expected}"
+        );
+    }
+
+    fn entrant(deck: &str) -> Entrant {
+        Entrant {
+            deck: deck.to_string(),
+            ai: StrategyKind::Random,
+        }
+    }
+
+    #[test]
+    fn expected_score_matches_known_values() {
+        assert_close(expected_score(1500.0, 1500.0), 0.5);
+        // 400 points ahead is ten to one
+        assert_close(expected_score(1900.0, 1500.0), 10.0 / 11.0);
+        assert_close(expected_score(1500.0, 1900.0), 1.0 / 11.0);
+    }
+
+    #[test]
+    fn record_rates_games_in_order() {
+        let results = TournamentResults {
+            entrants: vec![entrant("a"), entrant("b")],
+            games: vec![
+                Outcome {
+                    player: 0,
+                    enemy: 1,
+                    winner: Side::Player,
+                },
+                Outcome {
+                    player: 1,
+                    enemy: 0,
+                    winner: Side::Enemy,
+                },
+            ],
+        };
+        let mut ladder = Ladder::default();
+        ladder.record(&results, 16.0);
+
+        // 8 points for the even first game, then a little less once ahead
+        let change = 16.0 * (1.0 - expected_score(1508.0, 1492.0));
+        assert_close(change, 7.6318);
+        let [winner, loser] = &ladder.entries[..] else {
+            panic!("expected two entries");
+        };
+        assert_eq!(winner.deck, "a");
+        assert_close(winner.rating, 1508.0 + change);
+        assert_close(loser.rating, 1492.0 - change);
+        assert_eq!((winner.wins, winner.losses, winner.draws), (2, 0, 0));
+        assert_eq!((loser.wins, loser.losses, loser.draws), (0, 2, 0));
+    }
+
+    #[test]
+    fn record_keeps_ratings_from_earlier_runs() {
+        let mut ladder = Ladder::default();
+        let results = TournamentResults {
+            entrants: vec![entrant("a"), entrant("b")],
+            games: vec![Outcome {
+                player: 0,
+                enemy: 1,
+                winner: Side::Draw,
+            }],
+        };
+        ladder.record(&results, 16.0);
+        ladder.entries[0].rating = 1600.0;
+        ladder.record(&results, 16.0);
+        assert_eq!(ladder.entries.len(), 2);
+        assert_eq!(ladder.entries[0].draws, 2);
+        // A draw against a weaker entrant costs rating
+        assert!(ladder.entries[0].rating < 1600.0);
+    }
+}
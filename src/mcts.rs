@@ -0,0 +1,301 @@
+use bevy::utils::{Duration, Instant};
+use bevy_turborand::prelude::*;
+
+use crate::{
+    rules::RuleSet,
+    sim::{begin_turn, finish_turn, make_move, GameEvent, PlayerState, Side},
+    strategy::{Move, PlayerView, Strategy},
+};
+
+/// Exploration constant for UCT, the usual sqrt(2).
+const EXPLORATION: f64 = std::f64::consts::SQRT_2;
+/// Turns a rollout plays before scoring the position by remaining health.
+const ROLLOUT_TURNS: usize = 60;
+
+/// Searches for the best move with Monte Carlo Tree Search.
+///
+/// Both players are searched as if they were trying to win, with random
+/// play for the rollouts. Each iteration reshuffles the draw piles and deals
+/// the opponent a new hand from their unseen cards, so the search only knows
+/// what the player would. With a time budget the result depends on how fast
+/// the machine is, so leave it unset when runs need to be reproducible.
+pub struct MctsStrategy {
+    pub iterations: usize,
+    pub time_budget: Option<Duration>,
+}
+
+impl Strategy for MctsStrategy {
+    fn choose_move(&mut self, view: &PlayerView, rng: &mut RngComponent) -> Move {
+        let root = Model::from_view(view);
+        let moves = root.legal_moves();
+        if moves.len() <= 1 {
+            return moves.first().copied().unwrap_or(Move::Pass);
+        }
+
+        let mut tree = vec![Node::new(Move::Pass, None, &root)];
+        let start = Instant::now();
+        for _ in 0..self.iterations {
+            if self
+                .time_budget
+                .is_some_and(|budget| start.elapsed() > budget)
+            {
+                break;
+            }
+            iterate(&mut tree, &root, rng);
+        }
+
+        // The most visited move is the most robust choice
+        tree[0]
+            .children
+            .iter()
+            .max_by_key(|&&child| tree[child].visits)
+            .map_or(Move::Pass, |&child| tree[child].mv)
+    }
+}
+
+struct Node {
+    mv: Move,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<Move>,
+    visits: u32,
+    /// Total reward for the player who made `mv`.
+    reward: f64,
+    /// Player who moves from this node's position.
+    to_move: usize,
+}
+
+impl Node {
+    fn new(mv: Move, parent: Option<usize>, model: &Model) -> Self {
+        Node {
+            mv,
+            parent,
+            children: Vec::new(),
+            untried: if model.winner().is_some() {
+                Vec::new()
+            } else {
+                model.legal_moves()
+            },
+            visits: 0,
+            reward: 0.0,
+            to_move: model.to_move,
+        }
+    }
+
+    fn uct(&self, parent_visits: u32) -> f64 {
+        self.reward / self.visits as f64
+            + EXPLORATION * ((parent_visits as f64).ln() / self.visits as f64).sqrt()
+    }
+}
+
+/// One round of selection, expansion, rollout and backpropagation.
+fn iterate(tree: &mut Vec<Node>, root: &Model, rng: &mut RngComponent) {
+    let mut model = root.clone();
+    // The opponent's hand is as hidden as their draw pile
+    let opponent = &mut model.players[1];
+    let hand = opponent.hand.len();
+    opponent.draw_pile.append(&mut opponent.hand);
+    for player in &mut model.players {
+        rng.shuffle(&mut player.draw_pile);
+    }
+    let opponent = &mut model.players[1];
+    let unseen = opponent.draw_pile.len();
+    opponent.hand = opponent.draw_pile.split_off(unseen - hand);
+    let mut node = 0;
+
+    while tree[node].untried.is_empty() && !tree[node].children.is_empty() {
+        let parent_visits = tree[node].visits;
+        node = *tree[node]
+            .children
+            .iter()
+            .max_by(|&&a, &&b| {
+                tree[a]
+                    .uct(parent_visits)
+                    .total_cmp(&tree[b].uct(parent_visits))
+            })
+            .unwrap();
+        model.apply(tree[node].mv);
+    }
+
+    if !tree[node].untried.is_empty() {
+        let index = rng.usize(0..tree[node].untried.len());
+        let mv = tree[node].untried.swap_remove(index);
+        model.apply(mv);
+        let child = tree.len();
+        tree.push(Node::new(mv, Some(node), &model));
+        tree[node].children.push(child);
+        node = child;
+    }
+
+    let rewards = model.rollout(rng);
+
+    let mut current = Some(node);
+    while let Some(index) = current {
+        let parent = tree[index].parent;
+        // Reward each move from the point of view of whoever made it
+        let mover = parent.map_or(tree[index].to_move, |parent| tree[parent].to_move);
+        tree[index].visits += 1;
+        tree[index].reward += rewards[mover];
+        current = parent;
+    }
+}
+
+/// A copy of a game that can be played forward cheaply.
+#[derive(Clone)]
+struct Model {
+    players: [PlayerState; 2],
+    to_move: usize,
+    turns_left: usize,
+    /// Index of the player who won, once one has.
+    won: Option<usize>,
+    rules: RuleSet,
+    /// Scratch log for the rules to write to, never read.
+    events: Vec<GameEvent>,
+}
+
+impl Model {
+    fn from_view(view: &PlayerView) -> Self {
+        Model {
+            players: [view.own.clone(), view.opponent.clone()],
+            to_move: 0,
+            turns_left: view.turns_left,
+            won: None,
+            rules: view.rules.clone(),
+            events: Vec::new(),
+        }
+    }
+
+    /// `Some(player)` once a player has won, `Some(None)` for a draw.
+    fn winner(&self) -> Option<Option<usize>> {
+        if self.won.is_some() {
+            Some(self.won)
+        } else if self.turns_left == 0 {
+            Some(None)
+        } else {
+            None
+        }
+    }
+
+    /// Passing plus every legal play of each distinct card.
+    fn legal_moves(&self) -> Vec<Move> {
+        let player = &self.players[self.to_move];
+        let mut moves = vec![Move::Pass];
+        for (card, lane) in player.plays() {
+            // Identical cards lead to identical games, only try one of them
+            if player.hand[..card].contains(&player.hand[card]) {
+                continue;
+            }
+            moves.push(Move::Play { card, lane });
+        }
+        moves
+    }
+
+    /// Plays `mv` for the player to move and finishes their turn, then
+    /// starts the next one, following the same steps as the real game.
+    fn apply(&mut self, mv: Move) {
+        let (active, other) = match self.to_move {
+            0 => {
+                let [a, b] = &mut self.players;
+                (a, b)
+            }
+            _ => {
+                let [a, b] = &mut self.players;
+                (b, a)
+            }
+        };
+        let events = &mut self.events;
+        events.clear();
+        // Moves found under another shuffle may not fit this one's hand,
+        // `make_move` passes instead
+        let winner = make_move(active, other, mv, &self.rules, events)
+            .or_else(|| finish_turn(active, other, &self.rules, events));
+        if let Some(winner) = winner {
+            self.won = Some(self.index(winner));
+            return;
+        }
+        begin_turn(other, active, &self.rules, events);
+        self.turns_left -= 1;
+        self.to_move = 1 - self.to_move;
+    }
+
+    fn index(&self, side: Side) -> usize {
+        if self.players[0].side == side {
+            0
+        } else {
+            1
+        }
+    }
+
+    /// Plays randomly to the end and returns each player's reward.
+    fn rollout(mut self, rng: &mut RngComponent) -> [f64; 2] {
+        for _ in 0..ROLLOUT_TURNS {
+            if self.winner().is_some() {
+                break;
+            }
+            let moves = self.legal_moves();
+            self.apply(moves[rng.usize(0..moves.len())]);
+        }
+        match self.winner() {
+            Some(Some(0)) => [1.0, 0.0],
+            Some(Some(_)) => [0.0, 1.0],
+            Some(None) => [0.5, 0.5],
+            None => {
+                // Unfinished, lean towards whoever has more health left
+                let lead = (self.players[0].health - self.players[1].health) as f64;
+                let score = 0.5 + 0.5 * (lead / 10.0).tanh();
+                [score, 1.0 - score]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cards::{Card, CardKind},
+        effects::{Effect, Trigger, TriggeredEffect},
+    };
+
+    fn with_effect(trigger: Trigger, effect: Effect<Box<Card>>, card: Card) -> Card {
+        Card {
+            effects: vec![TriggeredEffect { trigger, effect }],
+            ..card
+        }
+    }
+
+    #[test]
+    fn game_ends_where_the_real_game_would() {
+        // The opponent's Fireball finishes the root player off, so the Bomb
+        // the attack would destroy never gets to hit back
+        let bomb = with_effect(
+            Trigger::OnDeath,
+            Effect::Damage(5),
+            Card::unit("bomb", 0, 1),
+        );
+        let fireball = Card {
+            kind: CardKind::Spell,
+            ..with_effect(
+                Trigger::OnPlay,
+                Effect::Damage(2),
+                Card::unit("fireball", 0, 0),
+            )
+        };
+        let root = PlayerState::in_play(Side::Player, 2, vec![Some(bomb)]);
+        let mut opponent =
+            PlayerState::in_play(Side::Enemy, 2, vec![Some(Card::unit("brute", 3, 3))]);
+        opponent.hand.push(fireball);
+        let mut model = Model {
+            players: [root, opponent],
+            to_move: 1,
+            turns_left: 10,
+            won: None,
+            rules: RuleSet::default(),
+            events: Vec::new(),
+        };
+        model.apply(Move::Play { card: 0, lane: 0 });
+        assert_eq!(model.winner(), Some(Some(1)));
+        assert_eq!(model.players[1].health, 2);
+        assert!(model.players[0].area.cards[0].is_some());
+    }
+}
@@ -0,0 +1,266 @@
+//! Recording games as replays and stepping through them again.
+//!
+//! A game's only randomness comes from its seed and the moves its
+//! strategies pick, so a replay stores just those next to the decks and
+//! rules, and plays the moves back through the same rules code. The decks
+//! keep every card as it was built, so later edits to the card database
+//! don't change how a replay plays out.
+
+use std::{
+    collections::VecDeque,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use bevy::{prelude::*, utils::Duration};
+use bevy_turborand::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    decks::{Deck, MatchDecks},
+    events::{log_game_events, send_game_events, GameEventsPlugin},
+    game::{log_games, Game, SimulationConfig},
+    rules::RuleSet,
+    sim::GameState,
+    strategy::{Move, PlayerView, Strategy},
+};
+
+/// Everything needed to play a game again exactly as it went.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+pub struct Replay {
+    /// The game's own seed, not the master seed it was derived from.
+    pub seed: u64,
+    pub rules: RuleSet,
+    pub player_deck: Deck,
+    pub enemy_deck: Deck,
+    /// Both sides' moves in the order they were made.
+    pub moves: Vec<Move>,
+}
+
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Write(#[from] ron::Error),
+    #[error("{0}")]
+    Read(#[from] ron::error::SpannedError),
+}
+
+impl Replay {
+    pub fn record(seed: u64, state: &GameState, decks: &MatchDecks) -> Self {
+        Replay {
+            seed,
+            rules: state.rules.clone(),
+            player_deck: decks.player.clone(),
+            enemy_deck: decks.enemy.clone(),
+            moves: state.moves.clone(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        Ok(ron::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Writes the replay of game `id` into the folder `dir`, creating it if
+    /// needed.
+    pub fn save(&self, dir: &Path, id: usize) -> Result<(), ReplayError> {
+        fs::create_dir_all(dir)?;
+        fs::write(replay_path(dir, id), self.to_ron()?)?;
+        Ok(())
+    }
+
+    fn to_ron(&self) -> Result<String, ReplayError> {
+        // One line per field keeps the move list compact
+        Ok(ron::ser::to_string_pretty(
+            self,
+            PrettyConfig::new().depth_limit(1),
+        )?)
+    }
+
+    /// Deals the recorded game again, with both seats playing back the
+    /// recorded moves.
+    pub fn game(&self) -> GameState {
+        let decks = MatchDecks {
+            player: self.player_deck.clone(),
+            enemy: self.enemy_deck.clone(),
+        };
+        let moves = Arc::new(Mutex::new(self.moves.iter().copied().collect()));
+        let strategies: [Box<dyn Strategy>; 2] = [
+            Box::new(ReplayStrategy {
+                moves: moves.clone(),
+            }),
+            Box::new(ReplayStrategy { moves }),
+        ];
+        GameState::deal(self.seed, &decks, strategies, &self.rules)
+    }
+}
+
+pub fn replay_path(dir: &Path, id: usize) -> PathBuf {
+    dir.join(format!("game-{}.replay.ron", id))
+}
+
+/// Saves a replay of every game stepped as an entity.
+pub fn save_replays(games: Query<&Game>, decks: Res<MatchDecks>, config: Res<SimulationConfig>) {
+    let Some(dir) = &config.replays else {
+        return;
+    };
+    for game in &games {
+        let replay = Replay::record(game.seed, &game.state, &decks);
+        if let Err(err) = replay.save(dir, game.id) {
+            error!(
+                "Failed to write {}: {}",
+                replay_path(dir, game.id).display(),
+                err
+            );
+        }
+    }
+    info!("Wrote replays to {}", dir.display());
+}
+
+/// Plays back recorded moves, both seats sharing one queue since they pick
+/// their moves in the order they were recorded.
+struct ReplayStrategy {
+    moves: Arc<Mutex<VecDeque<Move>>>,
+}
+
+impl Strategy for ReplayStrategy {
+    fn choose_move(&mut self, _view: &PlayerView, _rng: &mut RngComponent) -> Move {
+        self.moves.lock().unwrap().pop_front().unwrap_or(Move::Pass)
+    }
+}
+
+/// Steps through a replay's game, paused to begin with.
+pub struct ReplayPlugin {
+    pub replay: Replay,
+    /// Time between phases while playing.
+    pub step: Duration,
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(GameEventsPlugin)
+            .insert_resource(self.replay.clone())
+            .insert_resource(ReplayControls {
+                playing: false,
+                step_requested: false,
+                timer: Timer::new(self.step, TimerMode::Repeating),
+            })
+            .add_systems(Startup, (start_replay, print_controls))
+            .add_systems(
+                Update,
+                (
+                    control_replay,
+                    (step_replay, log_games).run_if(replay_step_requested),
+                    send_game_events,
+                    log_game_events,
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[derive(Resource)]
+struct ReplayControls {
+    playing: bool,
+    /// Whether the game advances a phase this frame.
+    step_requested: bool,
+    timer: Timer,
+}
+
+fn print_controls() {
+    info!("Space plays or pauses the replay, right arrow steps it while paused");
+}
+
+fn start_replay(mut commands: Commands, replay: Res<Replay>) {
+    commands.spawn(Game {
+        id: 0,
+        seed: replay.seed,
+        state: replay.game(),
+    });
+}
+
+fn control_replay(
+    keys: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    games: Query<&Game>,
+    mut controls: ResMut<ReplayControls>,
+) {
+    if keys.just_pressed(KeyCode::Space) {
+        controls.playing = !controls.playing;
+        controls.timer.reset();
+        info!(
+            "{}",
+            if controls.playing {
+                "Playing"
+            } else {
+                "Paused"
+            }
+        );
+    }
+    controls.step_requested = if controls.playing {
+        controls.timer.tick(time.delta()).just_finished()
+    } else {
+        keys.just_pressed(KeyCode::Right)
+    } && games.iter().any(|game| !game.state.is_over());
+}
+
+fn replay_step_requested(controls: Res<ReplayControls>) -> bool {
+    controls.step_requested
+}
+
+fn step_replay(mut games: Query<&mut Game>) {
+    for mut game in &mut games {
+        game.state.step();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cards::Card,
+        strategy::{MctsBudget, StrategyKind},
+    };
+
+    #[test]
+    fn replay_plays_out_the_recorded_game() {
+        let deck = Deck {
+            cards: [(1, 1), (2, 1), (1, 3), (3, 2)]
+                .iter()
+                .cycle()
+                .take(12)
+                .enumerate()
+                .map(|(i, &(damage, health))| Card::unit(&i.to_string(), damage, health))
+                .collect(),
+            health: 5,
+        };
+        let decks = MatchDecks {
+            player: deck.clone(),
+            enemy: deck,
+        };
+        let rules = RuleSet::default();
+        let mcts = MctsBudget {
+            iterations: 20,
+            time_budget: None,
+        };
+        for (seed, ais) in [
+            (1, [StrategyKind::Random, StrategyKind::Random]),
+            (2, [StrategyKind::Mcts, StrategyKind::Random]),
+        ] {
+            let strategies = ais.map(|kind| kind.build(mcts));
+            let mut played = GameState::deal(seed, &decks, strategies, &rules);
+            played.run_to_completion();
+            let text = Replay::record(seed, &played, &decks).to_ron().unwrap();
+
+            let mut replayed = ron::from_str::<Replay>(&text).unwrap().game();
+            replayed.run_to_completion();
+            assert_eq!(replayed.result(0, seed), played.result(0, seed));
+            assert_eq!(replayed.moves, played.moves);
+            assert_eq!(replayed.events, played.events);
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::{collections::HashMap, fs::File, io::BufWriter, path::Path, sync::Arc};
+
+use bevy::prelude::*;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{
+    cli::OutputFormat,
+    game::SimulationConfig,
+    sim::{CardTally, Side},
+    stats::{coin_flip_p_value, Distribution, Proportion},
+};
+
+/// How a single game ended.
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct GameResult {
+    pub game: usize,
+    pub seed: u64,
+    pub winner: Side,
+    pub first: Side,
+    pub turns: usize,
+    pub player_health: i32,
+    pub enemy_health: i32,
+    pub player_cards_left: usize,
+    pub enemy_cards_left: usize,
+    /// What each side's cards did if they were tracked, see `card_stats`.
+    #[serde(skip)]
+    pub cards: Option<[HashMap<Arc<str>, CardTally>; 2]>,
+}
+
+/// Results of every game, filled in once they have all halted.
+#[derive(Resource, Default)]
+pub struct GameResults(pub Vec<GameResult>);
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Csv(#[from] csv::Error),
+    #[error("{0}")]
+    Json(#[from] serde_json::Error),
+}
+
+pub fn write_csv(path: &Path, results: &[GameResult]) -> Result<(), ExportError> {
+    let mut writer = csv::Writer::from_path(path)?;
+    for result in results {
+        writer.serialize(result)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn write_json(path: &Path, results: &[GameResult]) -> Result<(), ExportError> {
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, results)?;
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct Summary {
+    seed: u64,
+    games: usize,
+    player_wins: usize,
+    enemy_wins: usize,
+    draws: usize,
+    player_win_rate: Proportion,
+    enemy_win_rate: Proportion,
+    draw_rate: Proportion,
+    /// Game length in turns.
+    turns: Distribution,
+    first_player: FirstPlayerAdvantage,
+}
+
+/// Whether the side moving first wins more than half of the decisive games.
+///
+/// When the two sides play different decks this also measures the gap
+/// between the decks, so it is most meaningful for mirror matches.
+#[derive(Serialize)]
+struct FirstPlayerAdvantage {
+    wins: usize,
+    decisive_games: usize,
+    win_rate: Proportion,
+    p_value: f64,
+}
+
+impl Summary {
+    fn new(seed: u64, results: &[GameResult]) -> Self {
+        let count = |side: Side| {
+            results
+                .iter()
+                .filter(|result| result.winner == side)
+                .count()
+        };
+        let (player_wins, enemy_wins, draws) =
+            (count(Side::Player), count(Side::Enemy), count(Side::Draw));
+        let games = results.len();
+        let decisive_games = games - draws;
+        let first_wins = results
+            .iter()
+            .filter(|result| result.winner == result.first)
+            .count();
+        let turns: Vec<usize> = results.iter().map(|result| result.turns).collect();
+        Summary {
+            seed,
+            games,
+            player_wins,
+            enemy_wins,
+            draws,
+            player_win_rate: Proportion::wilson(player_wins, games),
+            enemy_win_rate: Proportion::wilson(enemy_wins, games),
+            draw_rate: Proportion::wilson(draws, games),
+            turns: Distribution::new(&turns),
+            first_player: FirstPlayerAdvantage {
+                wins: first_wins,
+                decisive_games,
+                win_rate: Proportion::wilson(first_wins, decisive_games),
+                p_value: coin_flip_p_value(first_wins, decisive_games),
+            },
+        }
+    }
+}
+
+pub fn print_win_rates(results: Res<GameResults>, config: Res<SimulationConfig>) {
+    let summary = Summary::new(config.seed, &results.0);
+    match config.format {
+        OutputFormat::Text => {
+            info!(
+                "Results: {} player wins, {} enemy wins, {} draws",
+                summary.player_wins, summary.enemy_wins, summary.draws
+            );
+            info!(
+                "Win rates (95% CI): player {}, enemy {}, draw {}",
+                summary.player_win_rate, summary.enemy_win_rate, summary.draw_rate
+            );
+            let turns = &summary.turns;
+            info!(
+                "Game length: mean {:.1}, median {:.1}, p10 {:.1}, p25 {:.1}, p75 {:.1}, p90 {:.1}, max {}",
+                turns.mean, turns.median, turns.p10, turns.p25, turns.p75, turns.p90, turns.max
+            );
+            let first = &summary.first_player;
+            info!(
+                "First player won {} of {} decisive games, {} (p = {:.4})",
+                first.wins, first.decisive_games, first.win_rate, first.p_value
+            );
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string(&summary).unwrap()),
+    }
+}
+
+pub fn export_results(results: Res<GameResults>, config: Res<SimulationConfig>) {
+    if let Some(path) = &config.csv {
+        match write_csv(path, &results.0) {
+            Ok(()) => info!("Wrote results to {}", path.display()),
+            Err(err) => error!("Failed to write {}: {}", path.display(), err),
+        }
+    }
+    if let Some(path) = &config.json {
+        match write_json(path, &results.0) {
+            Ok(()) => info!("Wrote results to {}", path.display()),
+            Err(err) => error!("Failed to write {}: {}", path.display(), err),
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+/// An asset stored as a single RON file, picked up by file extension.
+pub trait RonAsset: Asset + DeserializeOwned {
+    const EXTENSIONS: &'static [&'static str];
+
+    /// Checks invariants serde can't express, run once after parsing.
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+pub struct RonAssetLoader<A> {
+    _marker: PhantomData<fn() -> A>,
+}
+
+impl<A> Default for RonAssetLoader<A> {
+    fn default() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RonLoaderError {
+    #[error("Could not read file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse file: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("Invalid file: {0}")]
+    Invalid(String),
+}
+
+impl<A: RonAsset> AssetLoader for RonAssetLoader<A> {
+    type Asset = A;
+    type Settings = ();
+    type Error = RonLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<A, RonLoaderError>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let asset: A = ron::de::from_bytes(&bytes)?;
+            asset.validate().map_err(RonLoaderError::Invalid)?;
+            Ok(asset)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        A::EXTENSIONS
+    }
+}
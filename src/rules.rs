@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::ron_asset::{RonAsset, RonAssetLoader};
+
+/// Loads rule sets from `.rules.ron` files.
+pub struct RulesPlugin;
+
+impl Plugin for RulesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<RuleSet>()
+            .init_asset_loader::<RonAssetLoader<RuleSet>>();
+    }
+}
+
+/// Parameters of the game that can change without touching its code, as
+/// written in a `.rules.ron` file.
+///
+/// Fields left out of the file keep their default value.
+#[derive(Asset, TypePath, Resource, Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct RuleSet {
+    /// Health of a deck whose list doesn't set its own.
+    pub starting_health: i32,
+    /// Number of turns before a game is called a draw.
+    pub turn_limit: usize,
+    /// Number of lanes in each play area.
+    pub lanes: usize,
+    /// Most cards a hand can hold, a player with a full hand skips their
+    /// draw. Unlimited if `None`.
+    pub hand_size: Option<usize>,
+    /// Cards each player draws before the first turn.
+    pub opening_hand: usize,
+    /// Energy each player gains at the start of their turn.
+    pub energy_per_turn: i32,
+    /// Most energy a player can bank, anything gained past it is lost.
+    pub max_energy: i32,
+    /// Whether a card brought down to exactly 0 health is destroyed.
+    pub zero_health_dies: bool,
+    pub first_player: FirstPlayer,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        RuleSet {
+            starting_health: 5,
+            turn_limit: 500,
+            lanes: 3,
+            hand_size: Some(5),
+            opening_hand: 3,
+            energy_per_turn: 1,
+            max_energy: 10,
+            zero_health_dies: false,
+            first_player: FirstPlayer::Player,
+        }
+    }
+}
+
+impl RuleSet {
+    /// Whether a card left with `health` stays in its lane.
+    pub fn survives(&self, health: i32) -> bool {
+        if self.zero_health_dies {
+            health > 0
+        } else {
+            health >= 0
+        }
+    }
+}
+
+impl RonAsset for RuleSet {
+    const EXTENSIONS: &'static [&'static str] = &["rules.ron"];
+
+    fn validate(&self) -> Result<(), String> {
+        if self.starting_health <= 0 {
+            return Err("starting_health must be positive".to_string());
+        }
+        if self.lanes == 0 {
+            return Err("there must be at least one lane".to_string());
+        }
+        if self.hand_size == Some(0) {
+            return Err("hand_size must be at least 1".to_string());
+        }
+        if self.hand_size.is_some_and(|size| self.opening_hand > size) {
+            return Err("opening_hand doesn't fit in hand_size".to_string());
+        }
+        if self.energy_per_turn < 0 || self.max_energy < 0 {
+            return Err("energy can't be negative".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Side that takes the first turn of every game.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FirstPlayer {
+    Player,
+    Enemy,
+    /// A coin toss per game, from that game's seed.
+    Random,
+}
+
+/// The rule set to load, and any values the command line overrides in it.
+#[derive(Resource, Clone)]
+pub struct RulesSelection {
+    /// Asset path of the `.rules.ron` file.
+    pub path: String,
+    pub turn_limit: Option<usize>,
+    pub lanes: Option<usize>,
+}
+
+impl RulesSelection {
+    pub fn apply(&self, rules: &RuleSet) -> RuleSet {
+        RuleSet {
+            turn_limit: self.turn_limit.unwrap_or(rules.turn_limit),
+            lanes: self.lanes.unwrap_or(rules.lanes),
+            ..rules.clone()
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct RulesHandle(pub Handle<RuleSet>);
@@ -0,0 +1,1059 @@
+//! The rules of the game as plain Rust, with no ECS involved.
+//!
+//! Bevy systems wrap a `GameState` per game, while batch runs can drive
+//! thousands of them directly.
+
+use std::{collections::HashMap, sync::Arc};
+
+use bevy::log::warn;
+use bevy_turborand::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    cards::{Card, CardKind, Keyword},
+    decks::{Deck, MatchDecks},
+    effects::{resolve_effects, PendingEffect, Trigger},
+    results::GameResult,
+    rules::{FirstPlayer, RuleSet},
+    seed::derive_seed,
+    strategy::{Move, PlayerView, Strategy},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Side {
+    Player,
+    Enemy,
+    Draw,
+}
+
+impl Side {
+    pub fn index(self) -> usize {
+        match self {
+            Side::Player => 0,
+            Side::Enemy => 1,
+            Side::Draw => unreachable!(),
+        }
+    }
+
+    fn opponent(self) -> Side {
+        match self {
+            Side::Player => Side::Enemy,
+            Side::Enemy => Side::Player,
+            Side::Draw => unreachable!(),
+        }
+    }
+}
+
+/// Something that happened in a game, logged in the order it happened.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    CardPlayed {
+        side: Side,
+        lane: usize,
+        card: Arc<str>,
+    },
+    /// A card in play took a hit.
+    CardBlocked {
+        side: Side,
+        lane: usize,
+        card: Arc<str>,
+        attacker: Arc<str>,
+        damage: i32,
+        /// Health the card has left, destroyed if it no longer survives.
+        health: i32,
+    },
+    CardDestroyed {
+        side: Side,
+        lane: usize,
+        card: Arc<str>,
+    },
+    /// The deck of `side` took damage from a card's attack or effect.
+    DirectDamage {
+        side: Side,
+        /// Lane of the card the damage came from, on the other side.
+        lane: usize,
+        source: Arc<str>,
+        damage: i32,
+        health: i32,
+    },
+    GameEnded {
+        winner: Side,
+        turns: usize,
+    },
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum GamePhase {
+    Play,
+    Attack,
+    Halt,
+}
+
+/// One row of lanes, each lined up with the same lane on the other side.
+#[derive(Default, Debug, Clone)]
+pub struct PlayArea {
+    pub cards: Vec<Option<Card>>,
+}
+
+impl PlayArea {
+    pub fn new(lanes: usize) -> Self {
+        PlayArea {
+            cards: vec![None; lanes],
+        }
+    }
+}
+
+/// One side's cards and health, everything the rules act on.
+#[derive(Clone, Debug)]
+pub struct PlayerState {
+    pub side: Side,
+    pub health: i32,
+    /// Spent to play cards. Each turn adds `energy_per_turn`, and whatever
+    /// is left over banks up to `max_energy`.
+    pub energy: i32,
+    /// Cards still to be drawn, the next one last.
+    pub draw_pile: Vec<Card>,
+    pub hand: Vec<Card>,
+    /// Cards destroyed in combat, never drawn again.
+    pub discard: Vec<Card>,
+    pub area: PlayArea,
+    /// Effects this player's cards triggered, waiting for `resolve_effects`.
+    pub pending: Vec<PendingEffect>,
+}
+
+impl PlayerState {
+    /// Shuffles `deck` into a draw pile and draws the opening hand.
+    pub fn new(deck: Deck, side: Side, rules: &RuleSet, rng: &mut RngComponent) -> Self {
+        let mut draw_pile = deck.cards;
+        rng.shuffle(&mut draw_pile);
+        let mut state = PlayerState {
+            side,
+            health: deck.health,
+            energy: 0,
+            draw_pile,
+            hand: Vec::new(),
+            discard: Vec::new(),
+            area: PlayArea::new(rules.lanes),
+            pending: Vec::new(),
+        };
+        for _ in 0..rules.opening_hand {
+            state.draw(rules);
+        }
+        state
+    }
+
+    /// Gains this turn's energy, readies cards in play and draws a card.
+    pub fn start_turn(&mut self, rules: &RuleSet) {
+        self.energy = (self.energy + rules.energy_per_turn).min(rules.max_energy);
+        for card in self.area.cards.iter_mut().flatten() {
+            card.summoning_sick = false;
+            if card.has(Keyword::Regenerate) {
+                card.health = card.max_health;
+            }
+        }
+        self.trigger_in_play(Trigger::StartOfTurn);
+        self.draw(rules);
+    }
+
+    pub fn end_turn(&mut self) {
+        self.trigger_in_play(Trigger::EndOfTurn);
+    }
+
+    /// Queues the `trigger` effects of every card in play.
+    fn trigger_in_play(&mut self, trigger: Trigger) {
+        for (lane, card) in self.area.cards.iter().enumerate() {
+            if let Some(card) = card {
+                queue_effects(&mut self.pending, card, lane, trigger);
+            }
+        }
+    }
+
+    /// Moves the top card of the draw pile into the hand, unless the hand is
+    /// full or there is nothing left to draw.
+    pub fn draw(&mut self, rules: &RuleSet) {
+        if rules.hand_size.is_some_and(|size| self.hand.len() >= size) {
+            return;
+        }
+        if let Some(card) = self.draw_pile.pop() {
+            self.hand.push(card);
+        }
+    }
+
+    /// Whether `hand[card]` is affordable and can go into `lane`: units need
+    /// it empty, equipment needs a unit to attach to and spells take any lane.
+    pub fn can_play(&self, card: usize, lane: usize) -> bool {
+        let Some(card) = self.hand.get(card) else {
+            return false;
+        };
+        let Some(slot) = self.area.cards.get(lane) else {
+            return false;
+        };
+        card.cost <= self.energy
+            && match card.kind {
+                CardKind::Unit => slot.is_none(),
+                CardKind::Spell => true,
+                CardKind::Equipment => slot.is_some(),
+            }
+    }
+
+    /// Pays for `hand[card]` and plays it into `lane`, queueing its on-play
+    /// effects.
+    pub fn play(&mut self, card: usize, lane: usize, events: &mut Vec<GameEvent>) {
+        let mut card = self.hand.remove(card);
+        self.energy -= card.cost;
+        events.push(GameEvent::CardPlayed {
+            side: self.side,
+            lane,
+            card: card.id.clone(),
+        });
+        queue_effects(&mut self.pending, &card, lane, Trigger::OnPlay);
+        match card.kind {
+            CardKind::Unit => {
+                card.summoning_sick = !card.has(Keyword::Haste);
+                self.area.cards[lane] = Some(card);
+            }
+            CardKind::Spell => self.discard.push(card),
+            CardKind::Equipment => self.area.cards[lane].as_mut().unwrap().equip(card),
+        }
+    }
+
+    /// Every card and lane pair `play` accepts, except that spells whose
+    /// effects don't depend on the lane only come up once, in the first lane.
+    pub fn plays(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.affordable().flat_map(move |(card, definition)| {
+            let lanes = if definition.kind == CardKind::Spell && !definition.summons() {
+                1
+            } else {
+                self.area.cards.len()
+            };
+            (0..lanes)
+                .filter(move |&lane| self.can_play(card, lane))
+                .map(move |lane| (card, lane))
+        })
+    }
+
+    /// Cards in hand that there is enough energy for, with their index.
+    pub fn affordable(&self) -> impl Iterator<Item = (usize, &Card)> + '_ {
+        self.hand
+            .iter()
+            .enumerate()
+            .filter(|(_, card)| card.cost <= self.energy)
+    }
+
+    /// Cards that have not been played or destroyed yet.
+    pub fn cards_left(&self) -> usize {
+        self.draw_pile.len() + self.hand.len()
+    }
+}
+
+/// Queues the effects `card` in `lane` fires on `trigger`.
+fn queue_effects(pending: &mut Vec<PendingEffect>, card: &Card, lane: usize, trigger: Trigger) {
+    for effect in card.effects_on(trigger) {
+        pending.push(PendingEffect {
+            source: card.id.clone(),
+            lane,
+            effect: effect.clone(),
+        });
+    }
+}
+
+/// Attacks with every ready card `attacker` has in play, lane by lane,
+/// stopping once `defender` has run out of health.
+pub fn attack(
+    attacker: &mut PlayerState,
+    defender: &mut PlayerState,
+    rules: &RuleSet,
+    events: &mut Vec<GameEvent>,
+) {
+    for slot in 0..attacker.area.cards.len() {
+        let Some(card) = &attacker.area.cards[slot] else {
+            continue;
+        };
+        if card.summoning_sick {
+            continue;
+        }
+        let damage = card.damage;
+        let mut to_deck = damage;
+        if let Some(lane) = target(card, slot, &defender.area, rules) {
+            let blocker = defender.area.cards[lane].as_mut().unwrap();
+            blocker.health -= damage;
+            to_deck = 0;
+            events.push(GameEvent::CardBlocked {
+                side: defender.side,
+                lane,
+                card: blocker.id.clone(),
+                attacker: card.id.clone(),
+                damage,
+                health: blocker.health,
+            });
+            if !rules.survives(blocker.health) {
+                if card.has(Keyword::Piercing) {
+                    to_deck = -blocker.health.min(0);
+                }
+                let mut destroyed = defender.area.cards[lane].take().unwrap();
+                events.push(GameEvent::CardDestroyed {
+                    side: defender.side,
+                    lane,
+                    card: destroyed.id.clone(),
+                });
+                queue_effects(&mut defender.pending, &destroyed, lane, Trigger::OnDeath);
+                defender.discard.append(&mut destroyed.attached);
+                defender.discard.push(destroyed);
+            }
+        }
+        if card.has(Keyword::Lifesteal) {
+            attacker.health += damage;
+        }
+        if to_deck > 0 {
+            defender.health -= to_deck;
+            events.push(GameEvent::DirectDamage {
+                side: defender.side,
+                lane: slot,
+                source: card.id.clone(),
+                damage: to_deck,
+                health: defender.health,
+            });
+            if defender.health <= 0 {
+                return;
+            }
+        }
+    }
+}
+
+/// Starts `active`'s turn and resolves the effects that triggers.
+///
+/// A turn is `begin_turn`, then `make_move` with the move `active` picks, then
+/// `finish_turn`, the game halting as soon as either of the last two returns
+/// a winner.
+pub fn begin_turn(
+    active: &mut PlayerState,
+    other: &mut PlayerState,
+    rules: &RuleSet,
+    events: &mut Vec<GameEvent>,
+) {
+    active.start_turn(rules);
+    resolve_effects(active, other, rules, events);
+}
+
+/// Plays `mv` for `active`, if it is legal, and resolves its effects.
+/// Returns the winner if that ran a deck out of health.
+pub fn make_move(
+    active: &mut PlayerState,
+    other: &mut PlayerState,
+    mv: Move,
+    rules: &RuleSet,
+    events: &mut Vec<GameEvent>,
+) -> Option<Side> {
+    if let Move::Play { card, lane } = mv {
+        if active.can_play(card, lane) {
+            active.play(card, lane, events);
+        }
+    }
+    resolve_effects(active, other, rules, events);
+    winner(active, other)
+}
+
+/// Attacks with `active`'s cards, ends their turn and resolves the effects
+/// that triggers. Returns the winner if that ran a deck out of health.
+pub fn finish_turn(
+    active: &mut PlayerState,
+    other: &mut PlayerState,
+    rules: &RuleSet,
+    events: &mut Vec<GameEvent>,
+) -> Option<Side> {
+    attack(active, other, rules, events);
+    active.end_turn();
+    resolve_effects(active, other, rules, events);
+    winner(active, other)
+}
+
+/// The side that has won once a deck is out of health, `active` if both are.
+pub fn winner(active: &PlayerState, other: &PlayerState) -> Option<Side> {
+    if other.health <= 0 {
+        Some(active.side)
+    } else if active.health <= 0 {
+        Some(other.side)
+    } else {
+        None
+    }
+}
+
+/// Lane of the card hit by `attacker` from `slot`, or `None` if it hits the
+/// deck.
+fn target(attacker: &Card, slot: usize, defenders: &PlayArea, rules: &RuleSet) -> Option<usize> {
+    let in_play = || {
+        defenders
+            .cards
+            .iter()
+            .enumerate()
+            .filter_map(|(lane, card)| card.as_ref().map(|card| (lane, card)))
+    };
+    // Taunt cards take every hit, the one opposite first
+    let taunts: Vec<usize> = in_play()
+        .filter(|(_, card)| card.has(Keyword::Taunt))
+        .map(|(lane, _)| lane)
+        .collect();
+    if let Some(&first) = taunts.first() {
+        return Some(if taunts.contains(&slot) { slot } else { first });
+    }
+    if attacker.has(Keyword::Ranged) {
+        let pick = in_play()
+            .filter(|(_, card)| !rules.survives(card.health - attacker.damage))
+            .max_by_key(|&(lane, card)| (card.damage, std::cmp::Reverse(lane)));
+        if let Some((lane, _)) = pick {
+            return Some(lane);
+        }
+    }
+    defenders.cards[slot].as_ref().map(|_| slot)
+}
+
+/// One side of a game: their cards and the AI playing them.
+pub struct Seat {
+    pub state: PlayerState,
+    pub strategy: Box<dyn Strategy>,
+    pub rng: RngComponent,
+}
+
+impl Seat {
+    pub fn new(
+        deck: Deck,
+        side: Side,
+        strategy: Box<dyn Strategy>,
+        seed: u64,
+        rules: &RuleSet,
+    ) -> Self {
+        let mut rng = RngComponent::with_seed(seed);
+        Seat {
+            state: PlayerState::new(deck, side, rules, &mut rng),
+            strategy,
+            rng,
+        }
+    }
+}
+
+pub struct GameState {
+    pub seats: [Seat; 2],
+    pub phase: GamePhase,
+    /// Side taking its turn, or the winner once the game has halted.
+    pub side: Side,
+    pub turn_count: usize,
+    /// Side that took the first turn.
+    pub first: Side,
+    pub rules: RuleSet,
+    /// Everything that happened since the log was last drained.
+    pub events: Vec<GameEvent>,
+    /// Every move chosen so far, both sides' in the order they were made.
+    pub moves: Vec<Move>,
+    /// What each side's cards have done so far, by card id, if anyone asked
+    /// with `track_cards`.
+    pub cards: Option<[HashMap<Arc<str>, CardTally>; 2]>,
+}
+
+/// What one side's copies of a card did over a game.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CardTally {
+    /// Whether the card was in the side's deck, which tokens never are.
+    pub in_deck: bool,
+    pub played: usize,
+    /// Damage dealt straight to the opposing deck, by attacks or effects.
+    pub direct_damage: i32,
+    /// Damage soaked up while blocking, up to the health the card had left.
+    pub damage_absorbed: i32,
+    pub destroyed: usize,
+}
+
+/// Seed stream of the first player coin toss, after those of the two seats.
+const COIN_TOSS_STREAM: u64 = 2;
+
+impl GameState {
+    pub fn new(player: Seat, enemy: Seat, rules: RuleSet, first: Side) -> Self {
+        GameState {
+            seats: [player, enemy],
+            phase: GamePhase::Play,
+            side: first,
+            turn_count: 0,
+            first,
+            rules,
+            events: Vec::new(),
+            moves: Vec::new(),
+            cards: None,
+        }
+    }
+
+    /// Starts tallying what each card does, from the cards in either deck.
+    pub fn track_cards(&mut self) {
+        self.cards = Some(self.seats.each_ref().map(|seat| {
+            let state = &seat.state;
+            state
+                .draw_pile
+                .iter()
+                .chain(&state.hand)
+                .map(|card| {
+                    let tally = CardTally {
+                        in_deck: true,
+                        ..Default::default()
+                    };
+                    (card.id.clone(), tally)
+                })
+                .collect()
+        }));
+    }
+
+    /// Sets up a game whose randomness all derives from `seed`, so the same
+    /// seed, decks, rules and moves always play out the same way.
+    pub fn deal(
+        seed: u64,
+        decks: &MatchDecks,
+        [player_strategy, enemy_strategy]: [Box<dyn Strategy>; 2],
+        rules: &RuleSet,
+    ) -> Self {
+        let player = Seat::new(
+            decks.player.clone(),
+            Side::Player,
+            player_strategy,
+            derive_seed(seed, Side::Player as u64),
+            rules,
+        );
+        let enemy = Seat::new(
+            decks.enemy.clone(),
+            Side::Enemy,
+            enemy_strategy,
+            derive_seed(seed, Side::Enemy as u64),
+            rules,
+        );
+        let first = match rules.first_player {
+            FirstPlayer::Player => Side::Player,
+            FirstPlayer::Enemy => Side::Enemy,
+            FirstPlayer::Random => {
+                let mut coin = RngComponent::with_seed(derive_seed(seed, COIN_TOSS_STREAM));
+                if coin.bool() {
+                    Side::Player
+                } else {
+                    Side::Enemy
+                }
+            }
+        };
+        GameState::new(player, enemy, rules.clone(), first)
+    }
+
+    pub fn player(&self, side: Side) -> &PlayerState {
+        &self.seats[side.index()].state
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.phase == GamePhase::Halt
+    }
+
+    /// Advances the game by one phase.
+    pub fn step(&mut self) {
+        let logged = self.events.len();
+        self.advance();
+        if self.cards.is_some() {
+            self.tally(logged);
+        }
+    }
+
+    fn advance(&mut self) {
+        match self.phase {
+            GamePhase::Play => {
+                if let Some(winner) = self.play() {
+                    self.halt(winner);
+                    return;
+                }
+                self.phase = GamePhase::Attack;
+            }
+            GamePhase::Attack => {
+                let [to_play, to_hit] = seats_mut(&mut self.seats, self.side);
+                let winner = finish_turn(
+                    &mut to_play.state,
+                    &mut to_hit.state,
+                    &self.rules,
+                    &mut self.events,
+                );
+                if let Some(winner) = winner {
+                    self.halt(winner);
+                    return;
+                }
+                self.turn_count += 1;
+                if self.turn_count > self.rules.turn_limit {
+                    self.phase = GamePhase::Halt;
+                    self.side = Side::Draw;
+                    self.events.push(GameEvent::GameEnded {
+                        winner: Side::Draw,
+                        turns: self.turn_count,
+                    });
+                    return;
+                }
+                self.phase = GamePhase::Play;
+                self.side = self.side.opponent();
+            }
+            GamePhase::Halt => {}
+        }
+    }
+
+    /// Adds the events logged from `start` on to the card tallies.
+    fn tally(&mut self, start: usize) {
+        let Some(cards) = &mut self.cards else {
+            return;
+        };
+        for event in &self.events[start..] {
+            let (side, card) = match event {
+                GameEvent::CardPlayed { side, card, .. }
+                | GameEvent::CardBlocked { side, card, .. }
+                | GameEvent::CardDestroyed { side, card, .. } => (*side, card),
+                // The source belongs to the side dealing the damage
+                GameEvent::DirectDamage { side, source, .. } => (side.opponent(), source),
+                GameEvent::GameEnded { .. } => continue,
+            };
+            let tally = cards[side.index()].entry(card.clone()).or_default();
+            match event {
+                GameEvent::CardPlayed { .. } => tally.played += 1,
+                GameEvent::CardBlocked { damage, health, .. } => {
+                    tally.damage_absorbed += (*damage).min(health + damage).max(0);
+                }
+                GameEvent::CardDestroyed { .. } => tally.destroyed += 1,
+                GameEvent::DirectDamage { damage, .. } => tally.direct_damage += damage,
+                GameEvent::GameEnded { .. } => {}
+            }
+        }
+    }
+
+    pub fn run_to_completion(&mut self) {
+        while !self.is_over() {
+            self.step();
+        }
+    }
+
+    pub fn result(&self, game: usize, seed: u64) -> GameResult {
+        let player = self.player(Side::Player);
+        let enemy = self.player(Side::Enemy);
+        GameResult {
+            game,
+            seed,
+            winner: self.side,
+            first: self.first,
+            turns: self.turn_count,
+            player_health: player.health,
+            enemy_health: enemy.health,
+            player_cards_left: player.cards_left(),
+            enemy_cards_left: enemy.cards_left(),
+            cards: self.cards.clone(),
+        }
+    }
+
+    fn halt(&mut self, winner: Side) {
+        self.side = winner;
+        self.phase = GamePhase::Halt;
+        self.events.push(GameEvent::GameEnded {
+            winner,
+            turns: self.turn_count,
+        });
+    }
+
+    /// Starts the turn and makes the move the side taking it picks, returning
+    /// the winner if that ended the game.
+    fn play(&mut self) -> Option<Side> {
+        let turns_left = self.rules.turn_limit + 1 - self.turn_count;
+        let [seat, opponent] = seats_mut(&mut self.seats, self.side);
+        begin_turn(
+            &mut seat.state,
+            &mut opponent.state,
+            &self.rules,
+            &mut self.events,
+        );
+        let view = PlayerView {
+            own: &seat.state,
+            opponent: &opponent.state,
+            turns_left,
+            rules: &self.rules,
+        };
+
+        let mv = seat.strategy.choose_move(&view, &mut seat.rng);
+        self.moves.push(mv);
+        if let Move::Play { card, lane } = mv {
+            if !seat.state.can_play(card, lane) {
+                warn!("Ignoring illegal move: card {} into lane {}", card, lane);
+            }
+        }
+        make_move(
+            &mut seat.state,
+            &mut opponent.state,
+            mv,
+            &self.rules,
+            &mut self.events,
+        )
+    }
+}
+
+/// The seat of `side` followed by its opponent's.
+fn seats_mut(seats: &mut [Seat; 2], side: Side) -> [&mut Seat; 2] {
+    let [player, enemy] = seats;
+    match side {
+        Side::Player => [player, enemy],
+        Side::Enemy => [enemy, player],
+        Side::Draw => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+impl PlayerState {
+    /// A side with nothing in hand or in its draw pile and `area` in play.
+    pub fn in_play(side: Side, health: i32, area: Vec<Option<Card>>) -> Self {
+        PlayerState {
+            side,
+            health,
+            energy: 0,
+            draw_pile: Vec::new(),
+            hand: Vec::new(),
+            discard: Vec::new(),
+            area: PlayArea { cards: area },
+            pending: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        effects::{Effect, TriggeredEffect},
+        strategy::RandomStrategy,
+    };
+
+    fn with(keyword: Keyword, card: Card) -> Card {
+        Card {
+            keywords: vec![keyword],
+            ..card
+        }
+    }
+
+    fn fight(attacker: &mut PlayerState, defender: &mut PlayerState, rules: &RuleSet) {
+        attack(attacker, defender, rules, &mut Vec::new());
+    }
+
+    fn game(player: PlayerState, enemy: PlayerState) -> GameState {
+        let seat = |state| Seat {
+            state,
+            strategy: Box::new(RandomStrategy),
+            rng: RngComponent::with_seed(0),
+        };
+        GameState::new(seat(player), seat(enemy), RuleSet::default(), Side::Player)
+    }
+
+    #[test]
+    fn taunt_takes_hits_from_every_lane() {
+        let rules = RuleSet::default();
+        let mut attacker = PlayerState::in_play(
+            Side::Player,
+            10,
+            vec![
+                Some(Card::unit("a", 1, 1)),
+                None,
+                Some(Card::unit("b", 1, 1)),
+            ],
+        );
+        let mut defender = PlayerState::in_play(
+            Side::Enemy,
+            10,
+            vec![
+                None,
+                Some(with(Keyword::Taunt, Card::unit("taunt", 0, 5))),
+                Some(Card::unit("c", 0, 5)),
+            ],
+        );
+        fight(&mut attacker, &mut defender, &rules);
+        assert_eq!(defender.area.cards[1].as_ref().unwrap().health, 3);
+        assert_eq!(defender.area.cards[2].as_ref().unwrap().health, 5);
+        assert_eq!(defender.health, 10);
+    }
+
+    #[test]
+    fn taunt_opposite_is_hit_first() {
+        let rules = RuleSet::default();
+        let mut attacker = PlayerState::in_play(
+            Side::Player,
+            10,
+            vec![None, None, Some(Card::unit("a", 1, 1))],
+        );
+        let taunt = with(Keyword::Taunt, Card::unit("taunt", 0, 5));
+        let mut defender = PlayerState::in_play(
+            Side::Enemy,
+            10,
+            vec![None, Some(taunt.clone()), Some(taunt)],
+        );
+        fight(&mut attacker, &mut defender, &rules);
+        assert_eq!(defender.area.cards[1].as_ref().unwrap().health, 5);
+        assert_eq!(defender.area.cards[2].as_ref().unwrap().health, 4);
+    }
+
+    #[test]
+    fn piercing_overflow_hits_the_deck() {
+        // (zero_health_dies, blocker health, deck health left)
+        let cases = [
+            (false, 1, 8),
+            (true, 1, 8),
+            // Left on exactly 0 health the blocker survives, or dies with
+            // nothing to spare
+            (false, 3, 10),
+            (true, 3, 10),
+        ];
+        for (zero_health_dies, blocker, health) in cases {
+            let rules = RuleSet {
+                zero_health_dies,
+                ..RuleSet::default()
+            };
+            let piercer = with(Keyword::Piercing, Card::unit("piercer", 3, 1));
+            let mut attacker = PlayerState::in_play(Side::Player, 10, vec![Some(piercer)]);
+            let mut defender = PlayerState::in_play(
+                Side::Enemy,
+                10,
+                vec![Some(Card::unit("blocker", 0, blocker))],
+            );
+            fight(&mut attacker, &mut defender, &rules);
+            assert_eq!(defender.health, health, "{zero_health_dies} {blocker}");
+            let destroyed = blocker < 3 || zero_health_dies;
+            assert_eq!(defender.area.cards[0].is_none(), destroyed);
+        }
+    }
+
+    #[test]
+    fn ranged_picks_off_the_strongest_card_it_can_destroy() {
+        let rules = RuleSet::default();
+        let archer = with(Keyword::Ranged, Card::unit("archer", 2, 1));
+        let mut attacker = PlayerState::in_play(Side::Player, 10, vec![Some(archer), None, None]);
+        let mut defender = PlayerState::in_play(
+            Side::Enemy,
+            10,
+            vec![
+                Some(Card::unit("wall", 1, 5)),
+                Some(Card::unit("brute", 3, 1)),
+                Some(Card::unit("squire", 2, 1)),
+            ],
+        );
+        fight(&mut attacker, &mut defender, &rules);
+        assert_eq!(defender.area.cards[0].as_ref().unwrap().health, 5);
+        assert!(defender.area.cards[1].is_none());
+        assert!(defender.area.cards[2].is_some());
+    }
+
+    #[test]
+    fn ranged_hits_the_opposite_lane_when_it_can_destroy_nothing() {
+        let rules = RuleSet::default();
+        let archer = with(Keyword::Ranged, Card::unit("archer", 1, 1));
+        let mut attacker = PlayerState::in_play(Side::Player, 10, vec![None, Some(archer)]);
+        let mut defender = PlayerState::in_play(
+            Side::Enemy,
+            10,
+            vec![Some(Card::unit("a", 0, 5)), Some(Card::unit("b", 0, 5))],
+        );
+        fight(&mut attacker, &mut defender, &rules);
+        assert_eq!(defender.area.cards[0].as_ref().unwrap().health, 5);
+        assert_eq!(defender.area.cards[1].as_ref().unwrap().health, 4);
+    }
+
+    #[test]
+    fn tally_counts_what_each_card_did() {
+        let mut player = PlayerState::in_play(
+            Side::Player,
+            10,
+            vec![
+                Some(Card::unit("striker", 5, 1)),
+                Some(Card::unit("poke", 1, 1)),
+                Some(Card::unit("archer", 2, 1)),
+            ],
+        );
+        player.draw_pile.push(Card::unit("striker", 5, 1));
+        let mut enemy = PlayerState::in_play(
+            Side::Enemy,
+            10,
+            vec![
+                Some(Card::unit("wall", 0, 2)),
+                Some(Card::unit("hatchling", 0, 3)),
+                None,
+            ],
+        );
+        enemy.draw_pile.push(Card::unit("wall", 0, 2));
+        enemy.hand.push(Card::unit("squire", 1, 1));
+        let mut game = game(player, enemy);
+        game.track_cards();
+        let [player, enemy] = &mut game.seats;
+        attack(
+            &mut player.state,
+            &mut enemy.state,
+            &game.rules,
+            &mut game.events,
+        );
+        game.tally(0);
+
+        let [player, enemy] = game.cards.as_ref().unwrap();
+        // Overkilled, it only soaks up the health it had
+        let wall = &enemy["wall"];
+        assert_eq!((wall.damage_absorbed, wall.destroyed), (2, 1));
+        assert!(wall.in_deck);
+        // A token was never in the deck
+        let hatchling = &enemy["hatchling"];
+        assert_eq!((hatchling.damage_absorbed, hatchling.destroyed), (1, 0));
+        assert!(!hatchling.in_deck);
+        // Never played, but still counted as in the deck
+        let squire = &enemy["squire"];
+        assert_eq!(squire.played, 0);
+        assert!(squire.in_deck);
+        assert_eq!(player["archer"].direct_damage, 2);
+        assert_eq!(player["striker"].direct_damage, 0);
+    }
+
+    #[test]
+    fn tally_counts_cards_played() {
+        let mut player = PlayerState::in_play(Side::Player, 10, vec![None]);
+        player.hand.push(Card::unit("squire", 1, 1));
+        let mut game = game(player, PlayerState::in_play(Side::Enemy, 10, vec![None]));
+        game.track_cards();
+        game.step();
+        let [player, _] = game.cards.as_ref().unwrap();
+        assert_eq!(player["squire"].played, 1);
+    }
+
+    fn deck(cards: usize) -> Deck {
+        Deck {
+            cards: (0..cards)
+                .map(|i| Card::unit(&i.to_string(), 1, 1))
+                .collect(),
+            health: 5,
+        }
+    }
+
+    #[test]
+    fn new_player_draws_the_opening_hand() {
+        let rules = RuleSet {
+            opening_hand: 3,
+            ..RuleSet::default()
+        };
+        let state = PlayerState::new(
+            deck(10),
+            Side::Player,
+            &rules,
+            &mut RngComponent::with_seed(0),
+        );
+        assert_eq!(state.hand.len(), 3);
+        assert_eq!(state.draw_pile.len(), 7);
+        assert_eq!(state.cards_left(), 10);
+        assert_eq!(state.health, 5);
+    }
+
+    #[test]
+    fn full_hand_skips_the_draw() {
+        let rules = RuleSet {
+            hand_size: Some(2),
+            opening_hand: 2,
+            ..RuleSet::default()
+        };
+        let mut state = PlayerState::new(
+            deck(5),
+            Side::Player,
+            &rules,
+            &mut RngComponent::with_seed(0),
+        );
+        state.start_turn(&rules);
+        assert_eq!((state.hand.len(), state.draw_pile.len()), (2, 3));
+
+        let unlimited = RuleSet {
+            hand_size: None,
+            ..rules
+        };
+        state.start_turn(&unlimited);
+        assert_eq!((state.hand.len(), state.draw_pile.len()), (3, 2));
+    }
+
+    #[test]
+    fn drawing_from_an_empty_pile_does_nothing() {
+        let rules = RuleSet::default();
+        let mut state = PlayerState::in_play(Side::Player, 5, vec![None]);
+        state.draw(&rules);
+        assert!(state.hand.is_empty());
+    }
+
+    #[test]
+    fn cards_left_counts_the_draw_pile_and_hand() {
+        let mut state = PlayerState::in_play(Side::Player, 5, vec![Some(Card::unit("a", 1, 1))]);
+        state.draw_pile.push(Card::unit("b", 1, 1));
+        state.hand.push(Card::unit("c", 1, 1));
+        state.discard.push(Card::unit("d", 1, 1));
+        assert_eq!(state.cards_left(), 2);
+    }
+
+    fn of_kind(kind: CardKind, card: Card) -> Card {
+        Card { kind, ..card }
+    }
+
+    #[test]
+    fn can_play_depends_on_the_kind_of_card() {
+        let mut state =
+            PlayerState::in_play(Side::Player, 5, vec![Some(Card::unit("a", 1, 1)), None]);
+        state.hand = vec![
+            Card::unit("unit", 1, 1),
+            of_kind(CardKind::Spell, Card::unit("spell", 0, 0)),
+            of_kind(CardKind::Equipment, Card::unit("sword", 2, 0)),
+        ];
+        // Units need an empty lane, equipment a unit, spells go anywhere
+        let playable: Vec<[bool; 2]> = (0..3)
+            .map(|card| [state.can_play(card, 0), state.can_play(card, 1)])
+            .collect();
+        assert_eq!(playable, [[false, true], [true, true], [true, false]]);
+        assert!(!state.can_play(0, 2));
+        assert!(!state.can_play(3, 1));
+
+        state.hand[0].cost = 1;
+        assert!(!state.can_play(0, 1));
+        state.energy = 1;
+        assert!(state.can_play(0, 1));
+    }
+
+    #[test]
+    fn spells_only_come_up_in_every_lane_when_they_summon() {
+        let mut state = PlayerState::in_play(Side::Player, 5, vec![None; 3]);
+        let spell = of_kind(CardKind::Spell, Card::unit("spell", 0, 0));
+        let summoner = Card {
+            effects: vec![TriggeredEffect {
+                trigger: Trigger::OnPlay,
+                effect: Effect::Summon(Box::new(Card::unit("token", 1, 1))),
+            }],
+            ..of_kind(CardKind::Spell, Card::unit("summoner", 0, 0))
+        };
+        state.hand = vec![spell, summoner];
+        let plays: Vec<(usize, usize)> = state.plays().collect();
+        assert_eq!(plays, [(0, 0), (1, 0), (1, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn equipment_adds_to_the_unit_and_goes_down_with_it() {
+        let rules = RuleSet::default();
+        let mut defender = PlayerState::in_play(Side::Enemy, 5, vec![Some(Card::unit("a", 1, 1))]);
+        defender.hand.push(Card {
+            keywords: vec![Keyword::Taunt],
+            ..of_kind(CardKind::Equipment, Card::unit("shield", 1, 3))
+        });
+        defender.play(0, 0, &mut Vec::new());
+        let unit = defender.area.cards[0].as_ref().unwrap();
+        assert_eq!((unit.damage, unit.health, unit.max_health), (2, 4, 4));
+        assert!(unit.has(Keyword::Taunt));
+        assert_eq!(&*unit.attached[0].id, "shield");
+        assert!(defender.hand.is_empty());
+
+        let mut attacker = PlayerState::in_play(Side::Player, 5, vec![Some(Card::unit("b", 5, 1))]);
+        fight(&mut attacker, &mut defender, &rules);
+        assert!(defender.area.cards[0].is_none());
+        let discarded: Vec<&str> = defender.discard.iter().map(|card| &*card.id).collect();
+        assert_eq!(discarded, ["shield", "a"]);
+    }
+
+    #[test]
+    fn spells_go_straight_to_the_discard_pile() {
+        let mut state = PlayerState::in_play(Side::Player, 5, vec![None]);
+        state
+            .hand
+            .push(of_kind(CardKind::Spell, Card::unit("spell", 0, 0)));
+        state.play(0, 0, &mut Vec::new());
+        assert!(state.area.cards[0].is_none());
+        assert_eq!(&*state.discard[0].id, "spell");
+    }
+}
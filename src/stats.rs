@@ -0,0 +1,167 @@
+use serde::Serialize;
+
+/// z value for a two-sided 95% confidence level.
+pub const Z_95: f64 = 1.959_963_984_540_054;
+
+/// A rate with its 95% Wilson score interval.
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct Proportion {
+    pub rate: f64,
+    pub low: f64,
+    pub high: f64,
+}
+
+impl Proportion {
+    pub fn wilson(successes: usize, trials: usize) -> Self {
+        if trials == 0 {
+            return Proportion {
+                rate: 0.0,
+                low: 0.0,
+                high: 1.0,
+            };
+        }
+        let n = trials as f64;
+        let p = successes as f64 / n;
+        let z2 = Z_95 * Z_95;
+        let centre = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+        let spread = Z_95 * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / (1.0 + z2 / n);
+        Proportion {
+            rate: p,
+            low: (centre - spread).max(0.0),
+            high: (centre + spread).min(1.0),
+        }
+    }
+}
+
+impl std::fmt::Display for Proportion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.1}% [{:.1}%, {:.1}%]",
+            self.rate * 100.0,
+            self.low * 100.0,
+            self.high * 100.0
+        )
+    }
+}
+
+/// Summary of a sample of non-negative counts, like game lengths.
+#[derive(Serialize, Clone, Copy, Debug, Default)]
+pub struct Distribution {
+    pub mean: f64,
+    pub median: f64,
+    pub p10: f64,
+    pub p25: f64,
+    pub p75: f64,
+    pub p90: f64,
+    pub max: f64,
+}
+
+impl Distribution {
+    pub fn new(values: &[usize]) -> Self {
+        if values.is_empty() {
+            return Distribution::default();
+        }
+        let mut sorted = values.to_vec();
+        sorted.sort_unstable();
+        let mean = sorted.iter().sum::<usize>() as f64 / sorted.len() as f64;
+        Distribution {
+            mean,
+            median: percentile(&sorted, 0.5),
+            p10: percentile(&sorted, 0.1),
+            p25: percentile(&sorted, 0.25),
+            p75: percentile(&sorted, 0.75),
+            p90: percentile(&sorted, 0.9),
+            max: *sorted.last().unwrap() as f64,
+        }
+    }
+}
+
+/// Linearly interpolated percentile of already sorted values.
+fn percentile(sorted: &[usize], fraction: f64) -> f64 {
+    let position = fraction * (sorted.len() - 1) as f64;
+    let below = position.floor() as usize;
+    let above = position.ceil() as usize;
+    let weight = position - below as f64;
+    sorted[below] as f64 * (1.0 - weight) + sorted[above] as f64 * weight
+}
+
+/// Two-sided p-value for seeing `successes` out of `trials` if both outcomes
+/// were equally likely, using the normal approximation with continuity
+/// correction.
+pub fn coin_flip_p_value(successes: usize, trials: usize) -> f64 {
+    if trials == 0 {
+        return 1.0;
+    }
+    let n = trials as f64;
+    let deviation = ((successes as f64 - n / 2.0).abs() - 0.5).max(0.0);
+    let z = deviation / (n / 4.0).sqrt();
+    erfc(z / std::f64::consts::SQRT_2).min(1.0)
+}
+
+/// Complementary error function, accurate to about 1e-7.
+///
+/// Numerical Recipes' Chebyshev fit, good enough for reporting p-values.
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let result = t
+        * (-z * z - 1.265_512_23
+            + t * (1.000_023_68
+                + t * (0.374_091_96
+                    + t * (0.096_784_18
+                        + t * (-0.186_288_06
+                            + t * (0.278_868_07
+                                + t * (-1.135_203_98
+                                    + t * (1.488_515_87
+                                        + t * (-0.822_152_23 + t * 0.170_872_77)))))))))
+            .exp();
+    if x >= 0.0 {
+        result
+    } else {
+        2.0 - result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "{actual} is not close to {expected}"
+        );
+    }
+
+    #[test]
+    fn wilson_interval_matches_known_values() {
+        let interval = Proportion::wilson(8, 10);
+        assert_close(interval.rate, 0.8);
+        assert_close(interval.low, 0.4902);
+        assert_close(interval.high, 0.9433);
+
+        let interval = Proportion::wilson(50, 100);
+        assert_close(interval.low, 0.4038);
+        assert_close(interval.high, 0.5962);
+
+        let interval = Proportion::wilson(0, 10);
+        assert_close(interval.low, 0.0);
+        assert_close(interval.high, 0.2775);
+    }
+
+    #[test]
+    fn wilson_interval_without_trials_is_uninformative() {
+        let interval = Proportion::wilson(0, 0);
+        assert_eq!((interval.low, interval.high), (0.0, 1.0));
+    }
+
+    #[test]
+    fn coin_flip_p_value_matches_known_values() {
+        // z = (10 - 0.5) / 5 = 1.9
+        assert_close(coin_flip_p_value(60, 100), 0.0574);
+        assert_close(coin_flip_p_value(40, 100), 0.0574);
+        assert_close(coin_flip_p_value(50, 100), 1.0);
+        assert_close(coin_flip_p_value(0, 0), 1.0);
+    }
+}
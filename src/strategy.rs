@@ -0,0 +1,214 @@
+use bevy::utils::Duration;
+use bevy_turborand::prelude::*;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cards::{Card, CardKind},
+    effects::{Effect, Trigger},
+    mcts::MctsStrategy,
+    rules::RuleSet,
+    sim::PlayerState,
+};
+
+/// Everything a player knows when it is their turn to play.
+///
+/// Draw piles are in the order they will be drawn, strategies should treat
+/// them as unordered to play fair.
+pub struct PlayerView<'a> {
+    pub own: &'a PlayerState,
+    pub opponent: &'a PlayerState,
+    /// Turns left before the game is called a draw, counting this one.
+    pub turns_left: usize,
+    pub rules: &'a RuleSet,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Move {
+    /// Play `hand[card]` into `lane`, see `PlayerState::can_play`.
+    Play {
+        card: usize,
+        lane: usize,
+    },
+    Pass,
+}
+
+/// Decides what a player does on their turn.
+pub trait Strategy: Send + Sync {
+    fn choose_move(&mut self, view: &PlayerView, rng: &mut RngComponent) -> Move;
+}
+
+/// Makes a random play out of every legal one.
+pub struct RandomStrategy;
+
+impl Strategy for RandomStrategy {
+    fn choose_move(&mut self, view: &PlayerView, rng: &mut RngComponent) -> Move {
+        let plays: Vec<(usize, usize)> = view.own.plays().collect();
+        if plays.is_empty() {
+            return Move::Pass;
+        }
+        let (card, lane) = plays[rng.usize(0..plays.len())];
+        Move::Play { card, lane }
+    }
+}
+
+/// Plays the hardest hitting card, into an unblocked lane when there is one.
+pub struct GreedyStrategy;
+
+impl Strategy for GreedyStrategy {
+    fn choose_move(&mut self, view: &PlayerView, rng: &mut RngComponent) -> Move {
+        // Prefer lanes where every point of damage reaches the deck
+        let hits = |damage: i32, opposing: Option<&Card>| {
+            let unblocked = if opposing.is_none() { 1000 } else { 0 };
+            unblocked + damage
+        };
+        best_move(view, rng, |play| match play.card.kind {
+            CardKind::Unit | CardKind::Equipment => hits(play.card.damage, play.opposing),
+            CardKind::Spell => play.spell_value(|effect| match effect {
+                Effect::Damage(amount) => hits(*amount, None),
+                Effect::Summon(token) => hits(token.damage, play.opposing),
+                Effect::Draw(_) | Effect::Heal(_) => 0,
+            }),
+        })
+    }
+}
+
+/// Blocks the most dangerous unblocked attacker with the toughest card.
+pub struct DefensiveStrategy;
+
+impl Strategy for DefensiveStrategy {
+    fn choose_move(&mut self, view: &PlayerView, rng: &mut RngComponent) -> Move {
+        let blocks = |blocker: &Card, opposing: Option<&Card>| {
+            let threat = opposing.map_or(0, |attacker| attacker.damage);
+            threat * 1000 + blocker.health
+        };
+        best_move(view, rng, |play| match play.card.kind {
+            CardKind::Unit => blocks(play.card, play.opposing),
+            // The unit it joins is already blocking
+            CardKind::Equipment => play.card.health,
+            CardKind::Spell => play.spell_value(|effect| match effect {
+                Effect::Heal(amount) => *amount,
+                Effect::Summon(token) => blocks(token, play.opposing),
+                Effect::Damage(_) | Effect::Draw(_) => 0,
+            }),
+        })
+    }
+}
+
+/// Answers each opposing card with one that beats it in the same lane.
+pub struct LaneMatchingStrategy;
+
+impl Strategy for LaneMatchingStrategy {
+    fn choose_move(&mut self, view: &PlayerView, rng: &mut RngComponent) -> Move {
+        let matches = |card: &Card, opposing: Option<&Card>| match opposing {
+            None => card.damage,
+            Some(opposing) => {
+                let mut score = 0;
+                if destroys(card, opposing, view.rules) {
+                    score += opposing.damage + opposing.health;
+                }
+                if !destroys(opposing, card, view.rules) {
+                    score += card.health;
+                }
+                score
+            }
+        };
+        best_move(view, rng, |play| match play.card.kind {
+            CardKind::Unit => matches(play.card, play.opposing),
+            CardKind::Equipment => {
+                let unit = play.unit.unwrap();
+                let mut equipped = unit.clone();
+                equipped.equip(play.card.clone());
+                matches(&equipped, play.opposing) - matches(unit, play.opposing)
+            }
+            CardKind::Spell => play.spell_value(|effect| match effect {
+                Effect::Damage(amount) => *amount,
+                Effect::Summon(token) => matches(token, play.opposing),
+                Effect::Draw(_) | Effect::Heal(_) => 0,
+            }),
+        })
+    }
+}
+
+/// Whether `attacker` hitting `defender` once removes it from its lane.
+pub fn destroys(attacker: &Card, defender: &Card, rules: &RuleSet) -> bool {
+    !rules.survives(defender.health - attacker.damage)
+}
+
+/// A legal play as `best_move` hands it over to be scored.
+struct Candidate<'a> {
+    card: &'a Card,
+    /// The unit already in the lane, which equipment joins.
+    unit: Option<&'a Card>,
+    /// The opposing card in the lane.
+    opposing: Option<&'a Card>,
+}
+
+impl Candidate<'_> {
+    /// Sums `value` over the effects of a spell, leaving out tokens that have
+    /// no empty lane to go into.
+    fn spell_value(&self, value: impl Fn(&Effect<Box<Card>>) -> i32) -> i32 {
+        self.card
+            .effects_on(Trigger::OnPlay)
+            .filter(|effect| !matches!(effect, Effect::Summon(_)) || self.unit.is_none())
+            .map(value)
+            .sum()
+    }
+}
+
+/// Makes the legal play with the highest `score`, breaking ties at random.
+fn best_move(view: &PlayerView, rng: &mut RngComponent, score: impl Fn(&Candidate) -> i32) -> Move {
+    let mut best = Vec::new();
+    let mut best_score = i32::MIN;
+    for (card, lane) in view.own.plays() {
+        let score = score(&Candidate {
+            card: &view.own.hand[card],
+            unit: view.own.area.cards[lane].as_ref(),
+            opposing: view.opponent.area.cards.get(lane).and_then(Option::as_ref),
+        });
+        if score > best_score {
+            best_score = score;
+            best.clear();
+        }
+        if score == best_score {
+            best.push(Move::Play { card, lane });
+        }
+    }
+    if best.is_empty() {
+        Move::Pass
+    } else {
+        best[rng.usize(0..best.len())]
+    }
+}
+
+/// Built-in strategies that can be picked from the command line.
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StrategyKind {
+    Random,
+    Greedy,
+    Defensive,
+    LaneMatching,
+    Mcts,
+}
+
+/// Search budget for `StrategyKind::Mcts`.
+#[derive(Clone, Copy, Debug)]
+pub struct MctsBudget {
+    pub iterations: usize,
+    pub time_budget: Option<Duration>,
+}
+
+impl StrategyKind {
+    pub fn build(self, mcts: MctsBudget) -> Box<dyn Strategy> {
+        match self {
+            StrategyKind::Random => Box::new(RandomStrategy),
+            StrategyKind::Greedy => Box::new(GreedyStrategy),
+            StrategyKind::Defensive => Box::new(DefensiveStrategy),
+            StrategyKind::LaneMatching => Box::new(LaneMatchingStrategy),
+            StrategyKind::Mcts => Box::new(MctsStrategy {
+                iterations: mcts.iterations,
+                time_budget: mcts.time_budget,
+            }),
+        }
+    }
+}
@@ -0,0 +1,375 @@
+//! Round robin tournaments that play every deck in a folder against every
+//! other deck, from both seats, and tabulate how each matchup went.
+//!
+//! Each deck is entered once per AI it is played with, so the same deck can
+//! also meet itself in the hands of a different AI.
+
+use std::{
+    any::TypeId,
+    path::{Path, PathBuf},
+};
+
+use bevy::{asset::LoadedFolder, prelude::*};
+
+use crate::{
+    batch::run_batch_with,
+    cards::{CardDatabase, CardDatabaseHandle, CardsPlugin, CARD_DATABASE},
+    decks::{Deck, DeckList, MatchDecks},
+    game::{loaded_rules, print_seed, AppFailed, AppState, SimulationConfig},
+    results::ExportError,
+    rules::{RuleSet, RulesHandle, RulesPlugin, RulesSelection},
+    sim::Side,
+    stats::Proportion,
+    strategy::StrategyKind,
+};
+
+/// Loads a folder of decks and plays out the tournament between them in a
+/// batch, leaving the `TournamentResults` and a `MatchupMatrix` behind.
+pub struct TournamentPlugin {
+    /// `games` counts the games of each pairing from each seat. The seat AIs
+    /// are unused, entrants bring their own.
+    pub config: SimulationConfig,
+    pub settings: TournamentSettings,
+    pub rules: RulesSelection,
+}
+
+impl Plugin for TournamentPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((CardsPlugin, RulesPlugin))
+            .add_state::<AppState>()
+            .insert_resource(self.config.clone())
+            .insert_resource(self.settings.clone())
+            .insert_resource(self.rules.clone())
+            .add_systems(Startup, (print_seed, load_decks))
+            .add_systems(Update, wait_for_decks.run_if(in_state(AppState::Loading)))
+            .add_systems(OnEnter(AppState::Simulating), run_tournament)
+            .add_systems(OnEnter(AppState::Finished), (print_matrix, export_matrix));
+    }
+}
+
+#[derive(Resource, Clone)]
+pub struct TournamentSettings {
+    /// Asset path of the folder holding the deck lists.
+    pub folder: String,
+    /// AIs every deck is entered with.
+    pub ais: Vec<StrategyKind>,
+    /// Where to write the matchup matrix, if anywhere.
+    pub csv: Option<PathBuf>,
+}
+
+#[derive(Resource)]
+struct DeckFolderHandle(Handle<LoadedFolder>);
+
+/// A deck and the AI playing it.
+#[derive(Clone, Debug)]
+pub struct Entrant {
+    pub deck: String,
+    pub ai: StrategyKind,
+}
+
+/// Every ordered pairing of two different entrants, with the first one
+/// playing as the player.
+#[derive(Resource)]
+struct Matchups {
+    entrants: Vec<Entrant>,
+    /// Whether entrants are told apart by their AI as well as their deck.
+    several_ais: bool,
+    pairings: Vec<(usize, usize)>,
+    decks: Vec<MatchDecks>,
+}
+
+/// Who played whom and who won, for every game of the tournament.
+#[derive(Resource)]
+pub struct TournamentResults {
+    pub entrants: Vec<Entrant>,
+    /// Games in rounds, each round playing every pairing once.
+    pub games: Vec<Outcome>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Outcome {
+    /// Index of the entrant in the player's seat.
+    pub player: usize,
+    pub enemy: usize,
+    pub winner: Side,
+}
+
+/// How one deck fared against another over every game they played.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Record {
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+}
+
+impl Record {
+    pub fn games(&self) -> usize {
+        self.wins + self.losses + self.draws
+    }
+
+    pub fn win_rate(&self) -> Proportion {
+        Proportion::wilson(self.wins, self.games())
+    }
+}
+
+/// Results of a tournament, entrants sorted by deck name.
+#[derive(Resource)]
+pub struct MatchupMatrix {
+    pub entrants: Vec<String>,
+    /// `records[entrant][opponent]`, counting both seats.
+    pub records: Vec<Vec<Record>>,
+}
+
+fn load_decks(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<TournamentSettings>,
+    rules: Res<RulesSelection>,
+) {
+    commands.insert_resource(CardDatabaseHandle(asset_server.load(CARD_DATABASE)));
+    commands.insert_resource(DeckFolderHandle(asset_server.load_folder(&settings.folder)));
+    commands.insert_resource(RulesHandle(asset_server.load(&rules.path)));
+}
+
+fn wait_for_decks(
+    mut commands: Commands,
+    database: Res<CardDatabaseHandle>,
+    databases: Res<Assets<CardDatabase>>,
+    folder: Res<DeckFolderHandle>,
+    folders: Res<Assets<LoadedFolder>>,
+    deck_lists: Res<Assets<DeckList>>,
+    rules: Res<RulesHandle>,
+    rule_sets: Res<Assets<RuleSet>>,
+    selection: Res<RulesSelection>,
+    settings: Res<TournamentSettings>,
+    asset_server: Res<AssetServer>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut exit: EventWriter<AppFailed>,
+) {
+    let handles = [database.0.clone().untyped(), folder.0.clone().untyped()];
+    let Some(rules) = loaded_rules(
+        &asset_server,
+        &handles,
+        &rules,
+        &rule_sets,
+        &selection,
+        &mut exit,
+    ) else {
+        return;
+    };
+
+    let mut lists: Vec<&DeckList> = folders
+        .get(&folder.0)
+        .unwrap()
+        .handles
+        .iter()
+        // Skip anything in the folder that isn't a deck list
+        .filter(|handle| handle.type_id() == TypeId::of::<DeckList>())
+        .filter_map(|handle| deck_lists.get(handle.id().typed::<DeckList>()))
+        .collect();
+    lists.sort_by(|a, b| a.name.cmp(&b.name));
+    // Entrants and ladder entries are told apart by deck name
+    if let Some(pair) = lists.windows(2).find(|pair| pair[0].name == pair[1].name) {
+        error!(
+            "Two decks in {} are named `{}`",
+            folder_name(&folder.0),
+            pair[0].name
+        );
+        exit.send(AppFailed);
+        return;
+    }
+    if lists.len() * settings.ais.len() < 2 {
+        error!(
+            "A tournament needs at least two entrants, found {} decks in {}",
+            lists.len(),
+            folder_name(&folder.0)
+        );
+        exit.send(AppFailed);
+        return;
+    }
+
+    let database = databases.get(&database.0).unwrap();
+    let decks = match lists
+        .iter()
+        .map(|list| Deck::from_list(list, database, &rules))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(decks) => decks,
+        Err(err) => {
+            error!("{}", err);
+            exit.send(AppFailed);
+            return;
+        }
+    };
+
+    // Entrant `i` plays deck `i / ais` with AI `i % ais`
+    let ais = settings.ais.len();
+    let mut matchups = Matchups {
+        entrants: lists
+            .iter()
+            .flat_map(|list| {
+                settings.ais.iter().map(|&ai| Entrant {
+                    deck: list.name.clone(),
+                    ai,
+                })
+            })
+            .collect(),
+        several_ais: ais > 1,
+        pairings: Vec::new(),
+        decks: Vec::new(),
+    };
+    for player in 0..matchups.entrants.len() {
+        for enemy in 0..matchups.entrants.len() {
+            if player == enemy {
+                continue;
+            }
+            let (player_deck, enemy_deck) = (player / ais, enemy / ais);
+            matchups.pairings.push((player, enemy));
+            matchups.decks.push(MatchDecks {
+                player: decks[player_deck].clone(),
+                enemy: decks[enemy_deck].clone(),
+            });
+        }
+    }
+    commands.insert_resource(matchups);
+    commands.insert_resource(rules);
+    next_state.set(AppState::Simulating);
+}
+
+fn folder_name(folder: &Handle<LoadedFolder>) -> String {
+    folder
+        .path()
+        .map_or_else(|| "the deck folder".to_string(), |path| path.to_string())
+}
+
+fn run_tournament(
+    mut commands: Commands,
+    config: Res<SimulationConfig>,
+    matchups: Res<Matchups>,
+    rules: Res<RuleSet>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let per_pairing = config.games;
+    let pairings = matchups.pairings.len();
+    // Number the games across every pairing so each gets its own seed
+    let config = SimulationConfig {
+        games: per_pairing * pairings,
+        ..config.clone()
+    };
+    let results = run_batch_with(&config, &rules, |id| {
+        let (player, enemy) = matchups.pairings[id / per_pairing];
+        let ais = [matchups.entrants[player].ai, matchups.entrants[enemy].ai];
+        (&matchups.decks[id / per_pairing], ais)
+    });
+
+    // Interleave the pairings so ratings don't drift with the order they
+    // were played in
+    let games: Vec<Outcome> = (0..per_pairing)
+        .flat_map(|round| (0..pairings).map(move |pairing| pairing * per_pairing + round))
+        .map(|id| {
+            let (player, enemy) = matchups.pairings[id / per_pairing];
+            Outcome {
+                player,
+                enemy,
+                winner: results[id].winner,
+            }
+        })
+        .collect();
+
+    let entrants = matchups.entrants.len();
+    let mut records = vec![vec![Record::default(); entrants]; entrants];
+    for &Outcome {
+        player,
+        enemy,
+        winner,
+    } in &games
+    {
+        match winner {
+            Side::Player => {
+                records[player][enemy].wins += 1;
+                records[enemy][player].losses += 1;
+            }
+            Side::Enemy => {
+                records[enemy][player].wins += 1;
+                records[player][enemy].losses += 1;
+            }
+            Side::Draw => {
+                records[player][enemy].draws += 1;
+                records[enemy][player].draws += 1;
+            }
+        }
+    }
+    commands.insert_resource(MatchupMatrix {
+        entrants: matchups
+            .entrants
+            .iter()
+            .map(|entrant| {
+                if matchups.several_ais {
+                    format!("{} ({:?})", entrant.deck, entrant.ai)
+                } else {
+                    entrant.deck.clone()
+                }
+            })
+            .collect(),
+        records,
+    });
+    commands.insert_resource(TournamentResults {
+        entrants: matchups.entrants.clone(),
+        games,
+    });
+    next_state.set(AppState::Finished);
+}
+
+fn print_matrix(matrix: Res<MatchupMatrix>) {
+    for (entrant, (name, records)) in matrix.entrants.iter().zip(&matrix.records).enumerate() {
+        let mut overall = Record::default();
+        for (opponent, record) in records.iter().enumerate() {
+            if opponent == entrant {
+                continue;
+            }
+            info!(
+                "{} vs {}: {} wins, {} losses, {} draws, win rate (95% CI) {}",
+                name,
+                matrix.entrants[opponent],
+                record.wins,
+                record.losses,
+                record.draws,
+                record.win_rate()
+            );
+            overall.wins += record.wins;
+            overall.losses += record.losses;
+            overall.draws += record.draws;
+        }
+        info!("{} overall: win rate (95% CI) {}", name, overall.win_rate());
+    }
+}
+
+/// Writes one row per entrant with its win rate against each opponent, the
+/// diagonal left empty.
+pub fn write_matrix_csv(path: &Path, matrix: &MatchupMatrix) -> Result<(), ExportError> {
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(
+        std::iter::once("entrant").chain(matrix.entrants.iter().map(String::as_str)),
+    )?;
+    for (entrant, (name, records)) in matrix.entrants.iter().zip(&matrix.records).enumerate() {
+        let rates = records.iter().enumerate().map(|(opponent, record)| {
+            if opponent == entrant {
+                String::new()
+            } else {
+                format!("{:.4}", record.win_rate().rate)
+            }
+        });
+        writer.write_record(std::iter::once(name.clone()).chain(rates))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn export_matrix(matrix: Res<MatchupMatrix>, settings: Res<TournamentSettings>) {
+    if let Some(path) = &settings.csv {
+        match write_matrix_csv(path, &matrix) {
+            Ok(()) => info!("Wrote matchup matrix to {}", path.display()),
+            Err(err) => error!("Failed to write {}: {}", path.display(), err),
+        }
+    }
+}
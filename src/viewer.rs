@@ -0,0 +1,171 @@
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{
+    game::{AppState, Game},
+    sim::Side,
+    tournament::MatchupMatrix,
+    BOARD_PADDING, BOARD_SIZE,
+};
+
+/// Side of a heatmap cell, big enough to fit a percentage.
+const CELL_SIZE: f32 = 90.0;
+
+/// Draws every game as a board on a grid.
+pub struct ViewerPlugin;
+
+impl Plugin for ViewerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup)
+            .add_systems(Update, (add_board_sprites, place_games, color_lanes));
+    }
+}
+
+/// Draws a tournament's matchup matrix as a heatmap once it has finished,
+/// each row an entrant and each column an opponent.
+pub struct HeatmapPlugin;
+
+impl Plugin for HeatmapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Finished), spawn_heatmap);
+    }
+}
+
+/// One lane of one side of a board, drawn over the board it belongs to.
+#[derive(Component)]
+struct LaneMarker {
+    side: Side,
+    lane: usize,
+}
+
+fn add_board_sprites(
+    mut commands: Commands,
+    games: Query<(Entity, &Game), Added<Game>>,
+    asset_server: Res<AssetServer>,
+) {
+    for (entity, game) in &games {
+        let lanes = game.state.rules.lanes;
+        let width = BOARD_SIZE / lanes as f32;
+        commands
+            .entity(entity)
+            .insert(SpriteBundle {
+                texture: asset_server.load("icon.png"),
+                sprite: Sprite {
+                    custom_size: Some(Vec2::splat(BOARD_SIZE)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with_children(|board| {
+                // Player lanes along the bottom half, enemy lanes along the top
+                for (side, y) in [(Side::Player, -0.25), (Side::Enemy, 0.25)] {
+                    for lane in 0..lanes {
+                        let x = (lane as f32 + 0.5) * width - BOARD_SIZE / 2.0;
+                        board.spawn((
+                            SpriteBundle {
+                                sprite: Sprite {
+                                    custom_size: Some(Vec2::new(width * 0.9, BOARD_SIZE * 0.45)),
+                                    ..Default::default()
+                                },
+                                transform: Transform::from_xyz(x, y * BOARD_SIZE, 1.0),
+                                ..Default::default()
+                            },
+                            LaneMarker { side, lane },
+                        ));
+                    }
+                }
+            });
+    }
+}
+
+fn color_lanes(games: Query<&Game>, mut markers: Query<(&Parent, &LaneMarker, &mut Sprite)>) {
+    for (parent, marker, mut sprite) in &mut markers {
+        let Ok(game) = games.get(parent.get()) else {
+            continue;
+        };
+        let occupied = game.state.player(marker.side).area.cards[marker.lane].is_some();
+        sprite.color = match (occupied, marker.side) {
+            (false, _) => Color::rgba(0.0, 0.0, 0.0, 0.5),
+            (true, Side::Player) => Color::rgb(0.2, 0.6, 1.0),
+            (true, _) => Color::rgb(1.0, 0.3, 0.3),
+        };
+    }
+}
+
+fn place_games(mut games: Query<(&mut Transform, &Game)>) {
+    // Lay the games out in the squarest grid that fits them all
+    let columns = (games.iter().count() as f32).sqrt().ceil().max(1.0) as usize;
+    for (mut transform, game) in &mut games {
+        let x = game.id % columns;
+        let y = game.id / columns;
+
+        transform.translation = Vec3::new(
+            x as f32 * (BOARD_SIZE + BOARD_PADDING),
+            y as f32 * (BOARD_SIZE + BOARD_PADDING),
+            0.0,
+        );
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.spawn(Camera2dBundle::default());
+}
+
+fn spawn_heatmap(mut commands: Commands, matrix: Res<MatchupMatrix>) {
+    let entrants = matrix.entrants.len();
+    let offset = (entrants as f32 - 1.0) / 2.0;
+    // Row 0 on top, column 0 on the left, centred on the camera
+    let cell = |row: usize, column: usize| {
+        Vec2::new(
+            (column as f32 - offset) * CELL_SIZE,
+            (offset - row as f32) * CELL_SIZE,
+        )
+    };
+    let style = TextStyle {
+        font_size: 16.0,
+        color: Color::WHITE,
+        ..Default::default()
+    };
+    let label = |commands: &mut Commands, text: &str, transform: Transform, anchor: Anchor| {
+        commands.spawn(Text2dBundle {
+            text: Text::from_section(text, style.clone()),
+            text_anchor: anchor,
+            transform,
+            ..Default::default()
+        });
+    };
+
+    for (index, name) in matrix.entrants.iter().enumerate() {
+        let left = cell(index, 0) - Vec2::new(CELL_SIZE * 0.6, 0.0);
+        let transform = Transform::from_translation(left.extend(1.0));
+        label(&mut commands, name, transform, Anchor::CenterRight);
+        // Column names run upwards so long ones don't overlap
+        let top = cell(0, index) + Vec2::new(0.0, CELL_SIZE * 0.6);
+        let transform = Transform::from_translation(top.extend(1.0))
+            .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2));
+        label(&mut commands, name, transform, Anchor::CenterLeft);
+    }
+    for (row, records) in matrix.records.iter().enumerate() {
+        for (column, record) in records.iter().enumerate() {
+            let position = cell(row, column);
+            let color = if row == column {
+                Color::DARK_GRAY
+            } else {
+                let rate = record.win_rate().rate as f32;
+                let percent = format!("{:.0}%", rate * 100.0);
+                let transform = Transform::from_translation(position.extend(1.0));
+                label(&mut commands, &percent, transform, Anchor::Center);
+                // Red for a losing matchup through to green for a winning one
+                Color::rgb(1.0 - rate, rate, 0.2)
+            };
+            commands.spawn(SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(Vec2::splat(CELL_SIZE * 0.95)),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(position.extend(0.0)),
+                ..Default::default()
+            });
+        }
+    }
+}